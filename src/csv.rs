@@ -0,0 +1,295 @@
+// Экспорт и импорт задач в CSV (RFC 4180): поля с запятыми, кавычками и переводами строк
+// берутся в кавычки, поэтому экспорт и повторный импорт дают тот же список задач
+use crate::{Priority, Subtask, Task};
+
+// Столбцы в порядке экспорта
+const COLUMNS: [&str; 16] = [
+    "description",
+    "completed",
+    "priority",
+    "due_date",
+    "icon",
+    "completion_note",
+    "created_at",
+    "completed_at",
+    "updated_at",
+    "tags",
+    "notes",
+    "color",
+    "subtasks",
+    "pinned",
+    "pomodoros",
+    "focus_secs",
+];
+
+// Список задач в CSV с заголовком
+pub fn to_csv(tasks: &[Task]) -> String {
+    let mut csv = COLUMNS.join(",");
+    csv.push_str("\r\n");
+    for task in tasks {
+        let tags = task.tags.join(" ");
+        let color = task.color.map(|[r, g, b]| format!("#{:02x}{:02x}{:02x}", r, g, b)).unwrap_or_default();
+        let subtasks = format_subtasks(&task.subtasks);
+        let pomodoros = task.pomodoros.to_string();
+        let focus_secs = task.focus_secs.to_string();
+        let fields = [
+            task.description.as_str(),
+            if task.completed { "true" } else { "false" },
+            task.priority.label(),
+            task.due_date.as_deref().unwrap_or_default(),
+            task.icon.as_deref().unwrap_or_default(),
+            task.completion_note.as_deref().unwrap_or_default(),
+            task.created_at.as_str(),
+            task.completed_at.as_deref().unwrap_or_default(),
+            task.updated_at.as_deref().unwrap_or_default(),
+            tags.as_str(),
+            task.notes.as_str(),
+            color.as_str(),
+            subtasks.as_str(),
+            if task.pinned { "true" } else { "false" },
+            pomodoros.as_str(),
+            focus_secs.as_str(),
+        ];
+        let row: Vec<String> = fields.iter().map(|field| quote(field)).collect();
+        csv.push_str(&row.join(","));
+        csv.push_str("\r\n");
+    }
+    csv
+}
+
+// Разбор CSV: корректные строки становятся задачами, ошибочные попадают в список ошибок.
+// Столбцы ищутся по заголовку, неизвестные игнорируются.
+pub fn from_csv(text: &str) -> Result<(Vec<Task>, Vec<String>), String> {
+    let mut rows = parse_rows(text)?.into_iter();
+    let header = rows.next().ok_or("The CSV file is empty")?;
+    let column = |name: &str| header.iter().position(|title| title.trim() == name);
+    let description_column = column("description").ok_or("The CSV file has no \"description\" column")?;
+
+    let mut tasks = Vec::new();
+    let mut errors = Vec::new();
+    for (n, row) in rows.enumerate() {
+        let line = n + 2; // С учетом заголовка и нумерации с единицы
+        if row.len() != header.len() {
+            errors.push(format!("row {}: expected {} fields, found {}", line, header.len(), row.len()));
+            continue;
+        }
+        let field = |name: &str| column(name).map(|i| row[i].as_str()).unwrap_or_default();
+        let optional = |name: &str| Some(field(name).to_string()).filter(|value| !value.is_empty());
+
+        let completed = match parse_flag(field("completed")) {
+            Some(completed) => completed,
+            None => {
+                errors.push(format!("row {}: invalid completed value \"{}\"", line, field("completed")));
+                continue;
+            }
+        };
+        let pinned = match parse_flag(field("pinned")) {
+            Some(pinned) => pinned,
+            None => {
+                errors.push(format!("row {}: invalid pinned value \"{}\"", line, field("pinned")));
+                continue;
+            }
+        };
+        let pomodoros = match field("pomodoros") {
+            "" => 0,
+            count => match count.parse() {
+                Ok(count) => count,
+                Err(_) => {
+                    errors.push(format!("row {}: invalid pomodoros value \"{}\"", line, count));
+                    continue;
+                }
+            },
+        };
+        let focus_secs = match field("focus_secs") {
+            "" => 0,
+            secs => match secs.parse() {
+                Ok(secs) => secs,
+                Err(_) => {
+                    errors.push(format!("row {}: invalid focus_secs value \"{}\"", line, secs));
+                    continue;
+                }
+            },
+        };
+        let subtasks = match parse_subtasks(field("subtasks")) {
+            Some(subtasks) => subtasks,
+            None => {
+                errors.push(format!("row {}: invalid subtasks \"{}\"", line, field("subtasks")));
+                continue;
+            }
+        };
+        let priority = match field("priority") {
+            "" => Priority::default(),
+            label => match Priority::ALL.into_iter().find(|priority| priority.label() == label) {
+                Some(priority) => priority,
+                None => {
+                    errors.push(format!("row {}: invalid priority \"{}\"", line, label));
+                    continue;
+                }
+            },
+        };
+
+        let color = match field("color") {
+            "" => None,
+            hex => match parse_color(hex) {
+                Some(color) => Some(color),
+                None => {
+                    errors.push(format!("row {}: invalid color \"{}\"", line, hex));
+                    continue;
+                }
+            },
+        };
+
+        tasks.push(Task {
+            description: row[description_column].clone(),
+            completed,
+            priority,
+            due_date: optional("due_date"),
+            icon: optional("icon"),
+            completion_note: optional("completion_note"),
+            created_at: field("created_at").to_string(),
+            completed_at: optional("completed_at"),
+            updated_at: optional("updated_at"),
+            tags: field("tags").split_whitespace().map(str::to_string).collect(),
+            notes: field("notes").to_string(),
+            color,
+            subtasks,
+            pinned,
+            pomodoros,
+            focus_secs,
+            ..Default::default()
+        });
+    }
+    Ok((tasks, errors))
+}
+
+// Флажок true/false; пустое поле означает false
+fn parse_flag(value: &str) -> Option<bool> {
+    match value {
+        "true" => Some(true),
+        "false" | "" => Some(false),
+        _ => None,
+    }
+}
+
+// Шаги задачи в одном поле: по шагу на строку, с отметкой как в Markdown-чеклисте
+fn format_subtasks(subtasks: &[Subtask]) -> String {
+    subtasks.iter()
+        .map(|subtask| format!("[{}] {}", if subtask.completed { "x" } else { " " }, subtask.description))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Разбор поля шагов; строка без отметки [ ] или [x] считается ошибкой
+fn parse_subtasks(field: &str) -> Option<Vec<Subtask>> {
+    field.lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let (completed, description) = if let Some(rest) = line.strip_prefix("[x] ") {
+                (true, rest)
+            } else {
+                (false, line.strip_prefix("[ ] ")?)
+            };
+            Some(Subtask { description: description.to_string(), completed })
+        })
+        .collect()
+}
+
+// Цвет в виде #rrggbb
+fn parse_color(hex: &str) -> Option<[u8; 3]> {
+    let hex = hex.strip_prefix('#')?;
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+// Поле в кавычках, если это нужно
+fn quote(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+// Разбиение текста на строки и поля с учетом кавычек
+fn parse_rows(text: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => in_quotes = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => in_quotes = true,
+            ',' => row.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' | '\r' => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            _ => field.push(c),
+        }
+    }
+    if in_quotes {
+        return Err("The CSV file ends inside a quoted field".to_string());
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    // Пустые строки (например, в конце файла) пропускаются
+    rows.retain(|row| !(row.len() == 1 && row[0].is_empty()));
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn export_and_import_keep_every_column() {
+        let mut task = Task::new("Plan trip, book \"hotel\"".to_string());
+        task.completed = true;
+        task.priority = Priority::High;
+        task.due_date = Some("2024-03-15".to_string());
+        task.icon = Some("✈".to_string());
+        task.completion_note = Some("Booked".to_string());
+        task.completed_at = Some("2024-03-12T10:00:00+00:00".to_string());
+        task.tags = vec!["@travel".to_string(), "@family".to_string()];
+        task.notes = "Line one\nLine two".to_string();
+        task.color = Some([40, 170, 200]);
+        task.subtasks = vec![
+            Subtask { description: "Flights, both ways".to_string(), completed: true },
+            Subtask { description: "Hotel".to_string(), completed: false },
+        ];
+        task.pinned = true;
+        task.pomodoros = 3;
+        task.focus_secs = 95 * 60;
+        let tasks = vec![task, Task::new("Buy milk".to_string())];
+
+        let csv = to_csv(&tasks);
+        let (imported, errors) = from_csv(&csv).unwrap();
+        assert!(errors.is_empty());
+        // Повторный экспорт совпадает с первым, значит ни одно поле не потерялось
+        assert_eq!(to_csv(&imported), csv);
+        assert_eq!(imported[0].subtasks.len(), 2);
+        assert!(imported[0].subtasks[0].completed);
+        assert!(imported[0].pinned);
+        assert_eq!(imported[0].pomodoros, 3);
+        assert_eq!(imported[0].focus_secs, 95 * 60);
+    }
+}
@@ -0,0 +1,89 @@
+// Нечеткий поиск: буквы каждого слова запроса ищутся в описании по порядку, но не обязательно
+// подряд. Слова запроса могут идти в любом порядке, в длинных словах допускается одна опечатка.
+
+// Минимальная средняя оценка на букву слова, ниже которой совпадение не считается
+const MIN_SCORE_PER_CHAR: i64 = 4;
+// Слова короче этого должны совпадать без опечаток
+const TYPO_MIN_LEN: usize = 4;
+// Штраф за пропущенную букву запроса
+const TYPO_PENALTY: i64 = 20;
+
+// Оценка совпадения описания с запросом; None — не совпадает. Чем больше, тем лучше.
+pub fn score(text: &str, query: &str) -> Option<i64> {
+    find(text, query).map(|(score, _)| score)
+}
+
+// Номера символов описания (не байтов), совпавших с запросом, по возрастанию
+pub fn match_indices(text: &str, query: &str) -> Option<Vec<usize>> {
+    find(text, query).map(|(_, indices)| indices)
+}
+
+// Оценка и номера совпавших символов. Поиск идет по тексту в нижнем регистре, где одна буква
+// может превратиться в несколько, поэтому для каждой строчной буквы хранится номер исходной.
+fn find(text: &str, query: &str) -> Option<(i64, Vec<usize>)> {
+    let (text, origin): (Vec<char>, Vec<usize>) = text.chars()
+        .enumerate()
+        .flat_map(|(n, c)| c.to_lowercase().map(move |lower| (lower, n)))
+        .unzip();
+    let mut total = 0;
+    let mut indices = Vec::new();
+    for word in query.split_whitespace() {
+        let word: Vec<char> = word.chars().flat_map(char::to_lowercase).collect();
+        let (score, positions) = word_score(&text, &word).or_else(|| typo_score(&text, &word))?;
+        total += score;
+        indices.extend(positions.into_iter().map(|position| origin[position]));
+    }
+    indices.sort_unstable();
+    indices.dedup();
+    Some((total, indices))
+}
+
+// Лучшая оценка слова среди всех возможных начальных позиций и позиции его букв
+fn word_score(text: &[char], word: &[char]) -> Option<(i64, Vec<usize>)> {
+    let best = (0..text.len())
+        .filter(|&start| text[start] == word[0])
+        .filter_map(|start| score_from(text, word, start))
+        .max_by_key(|(score, _)| *score)?;
+    (best.0 >= MIN_SCORE_PER_CHAR * word.len() as i64).then_some(best)
+}
+
+// Оценка слова, если совпадение начинается с позиции `start`: бонусы за буквы подряд
+// и за начало слова в описании, штраф за разрывы
+fn score_from(text: &[char], word: &[char], start: usize) -> Option<(i64, Vec<usize>)> {
+    let mut score = 0;
+    let mut position = start;
+    let mut positions: Vec<usize> = Vec::with_capacity(word.len());
+    for &c in word {
+        let found = (position..text.len()).find(|&i| text[i] == c)?;
+        score += 10;
+        match positions.last() {
+            Some(&previous) if found == previous + 1 => score += 15,
+            Some(&previous) => score -= (found - previous - 1).min(10) as i64,
+            None => {}
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 10;
+        }
+        positions.push(found);
+        position = found + 1;
+    }
+    Some((score, positions))
+}
+
+// Оценка слова с одной лишней или ошибочной буквой: лучшее совпадение без одной из букв
+fn typo_score(text: &[char], word: &[char]) -> Option<(i64, Vec<usize>)> {
+    if word.len() < TYPO_MIN_LEN {
+        return None;
+    }
+    (0..word.len())
+        .filter_map(|skip| {
+            let shorter: Vec<char> = word.iter()
+                .enumerate()
+                .filter(|&(i, _)| i != skip)
+                .map(|(_, &c)| c)
+                .collect();
+            word_score(text, &shorter)
+        })
+        .max_by_key(|(score, _)| *score)
+        .map(|(score, positions)| (score - TYPO_PENALTY, positions))
+}
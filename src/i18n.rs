@@ -0,0 +1,307 @@
+// Перевод надписей интерфейса. Ключом служит английский текст, поэтому для английского
+// языка, а также для строк без перевода, возвращается сам ключ.
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum Lang {
+    #[default]
+    English,
+    Russian,
+}
+
+impl Lang {
+    pub const ALL: [Lang; 2] = [Lang::English, Lang::Russian];
+
+    // Название языка на нем самом
+    pub fn label(self) -> &'static str {
+        match self {
+            Lang::English => "English",
+            Lang::Russian => "Русский",
+        }
+    }
+}
+
+// Перевод строки на выбранный язык
+pub fn translate(lang: Lang, key: &'static str) -> &'static str {
+    match lang {
+        Lang::English => key,
+        Lang::Russian => russian(key).unwrap_or(key),
+    }
+}
+
+// Перевод шаблона с подстановкой аргументов вместо `{}` по порядку
+pub fn format(lang: Lang, key: &'static str, args: &[&dyn std::fmt::Display]) -> String {
+    let mut result = String::new();
+    for (n, part) in translate(lang, key).split("{}").enumerate() {
+        if let Some(arg) = n.checked_sub(1).and_then(|i| args.get(i)) {
+            result.push_str(&arg.to_string());
+        }
+        result.push_str(part);
+    }
+    result
+}
+
+// Таблица русских переводов
+fn russian(key: &str) -> Option<&'static str> {
+    Some(match key {
+        // Заголовок и баннеры
+        "To-Do List" => "Список задач",
+        "The app crashed last time. Recover unsaved changes?" => "В прошлый раз приложение аварийно завершилось. Восстановить несохраненные изменения?",
+        "Recover" => "Восстановить",
+        "Discard" => "Отбросить",
+        "Retry" => "Повторить",
+        "{} could not be read. It was kept as {} so you can recover it manually." => "Не удалось прочитать {}. Файл сохранен как {}, его можно восстановить вручную.",
+        "Dismiss" => "Закрыть",
+        "You have {} tasks older than {} days — review?" => "У вас {} задач старше {} дн. — просмотреть?",
+        "Show only these tasks" => "Показать только эти задачи",
+        "Showing tasks older than {} days" => "Показаны задачи старше {} дн.",
+        "Toggle Theme" => "Сменить тему",
+        "Save Now" => "Сохранить",
+        "Unsaved changes" => "Есть несохраненные изменения",
+        "Switch to the next theme ({})" => "Переключить на следующую тему ({})",
+
+        // Списки
+        "New List" => "Новый список",
+        "Rename List" => "Переименовать список",
+        "Delete List" => "Удалить список",
+        "The last list can't be deleted" => "Последний список нельзя удалить",
+
+        // Настройки
+        "Settings" => "Настройки",
+        "Language:" => "Язык:",
+        "Progress:" => "Прогресс:",
+        "Bar" => "Полоса",
+        "Ring" => "Кольцо",
+        "Activity Log" => "Журнал действий",
+        "Append every change to {}" => "Записывать каждое изменение в {}",
+        "Ask for a Note on Completion" => "Спрашивать заметку при выполнении",
+        "Open with Today's Tasks" => "Открывать с задачами на сегодня",
+        "Show tasks due today and overdue ones on launch, or all tasks if there are none" => "При запуске показывать задачи на сегодня и просроченные, а если их нет — все задачи",
+        "Share Search Between Lists" => "Общий поиск для всех списков",
+        "Keep the search when switching lists instead of remembering one per list" => "Сохранять поиск при переключении списков, а не запоминать свой для каждого",
+        "Mute Overdue Notifications" => "Без уведомлений о просрочке",
+        "Don't show a desktop notification when a task becomes overdue" => "Не показывать системное уведомление, когда задача просрочена",
+        "Save Manually" => "Сохранять вручную",
+        "Write the task file only with \"Save Now\" and on exit" => "Записывать файл задач только кнопкой «Сохранить» и при выходе",
+        "Autosave Every:" => "Автосохранение каждые:",
+        "A longer interval writes less often, but more changes can be lost in a crash" => "Чем длиннее промежуток, тем реже запись, но тем больше изменений может пропасть при сбое",
+        "Count Subtasks in Progress" => "Учитывать шаги в прогрессе",
+        "Partially done tasks add to the progress by their finished subtasks" => "Частично выполненные задачи учитываются по выполненным шагам",
+        "Clock Format:" => "Формат часов:",
+        "24-hour" => "24 часа",
+        "12-hour" => "12 часов",
+        "With Date" => "С датой",
+        "Custom" => "Свой",
+        "chrono strftime format, e.g. %H:%M" => "Формат strftime из chrono, например %H:%M",
+        "New Day Starts At:" => "Новый день начинается в:",
+        "Tasks completed after midnight but before this hour count for the previous day" => "Задачи, выполненные после полуночи, но до этого часа, относятся к предыдущему дню",
+        "Remind About Tasks Older Than:" => "Напоминать о задачах старше:",
+        "days" => "дн.",
+        "Max Task Length:" => "Максимальная длина задачи:",
+        "no limit" => "без ограничения",
+        "Accent Color:" => "Цвет акцента:",
+        "Reset" => "Сбросить",
+        "Use the theme's accent color" => "Использовать цвет акцента темы",
+        "Templates:" => "Шаблоны:",
+        "Save the task input as a template to reuse it" => "Сохраните ввод задачи как шаблон, чтобы использовать его снова",
+        "Remove Template" => "Удалить шаблон",
+        "UI Scale:" => "Масштаб интерфейса:",
+        "{} / {} to zoom, {} to reset" => "{} / {} — изменить масштаб, {} — сбросить",
+        "Restore Defaults" => "Настройки по умолчанию",
+        "Reset all settings without touching tasks" => "Сбросить все настройки, не трогая задачи",
+        "Progress: {}%" => "Прогресс: {}%",
+        "🎉 All done!" => "🎉 Все сделано!",
+
+        // Помидоры
+        "Stop" => "Стоп",
+        "🍅 Pomodoro finished: {}" => "🍅 Помидор закончен: {}",
+        "Finished pomodoros" => "Законченные помидоры",
+        "⏱{} min" => "⏱{} мин",
+        "Time spent with the timer" => "Время, отработанное по таймеру",
+        "Stop Pomodoro" => "Остановить помидор",
+        "Start a 25-minute Pomodoro" => "Запустить помидор на 25 минут",
+
+        // Уведомления
+        "Task overdue" => "Задача просрочена",
+        "{} tasks are overdue" => "Просрочено задач: {}",
+
+        // Статистика
+        "Statistics" => "Статистика",
+        "Total tasks:" => "Всего задач:",
+        "Completed:" => "Выполнено:",
+        "Overdue:" => "Просрочено:",
+        "Completed today:" => "Выполнено сегодня:",
+        "Completed in the last {} days:" => "Выполнено за последние дни ({}):",
+        "{}: {} completed" => "{}: выполнено {}",
+
+        // Ввод задач и черновик
+        "Enter a new task... ({} to add)" => "Введите новую задачу... ({} — добавить)",
+        "{} characters, {} lines" => "символов: {}, строк: {}",
+        "{}/{} characters, {} lines" => "символов: {}/{}, строк: {}",
+        "Quick add: !high or !low sets the priority, @YYYY-MM-DD the due date, #tag adds a tag" => "Быстрый ввод: !high или !low задают приоритет, @ГГГГ-ММ-ДД — срок, #тег добавляет тег",
+        "Add Task" => "Добавить задачу",
+        "Add Task ({})" => "Добавить задачу ({})",
+        "Templates" => "Шаблоны",
+        "Save as Template" => "Сохранить как шаблон",
+        "Save the current input as a template" => "Сохранить текущий ввод как шаблон",
+        "Add Anyway" => "Все равно добавить",
+        "⚠ This task is already in the list. Add it anyway?" => "⚠ Такая задача уже есть в списке. Все равно добавить?",
+        "Scratchpad" => "Черновик",
+        "Jot something down..." => "Запишите что-нибудь...",
+        "Convert to Task" => "Сделать задачей",
+
+        // Поиск, сортировка и фильтры
+        "Search:" => "Поиск:",
+        "Narrow down with priority:high, tag:home or due:today (also tomorrow, overdue, none or YYYY-MM-DD)" => "Уточнение запроса: priority:high, tag:дом или due:today (а также tomorrow, overdue, none или ГГГГ-ММ-ДД)",
+        "Clear Search" => "Очистить поиск",
+        "Fuzzy" => "Нечетко",
+        "Allow typos and words in any order" => "Допускать опечатки и любой порядок слов",
+        "Sort: {}" => "Сортировка: {}",
+        "Manual" => "Вручную",
+        "Priority" => "Приоритет",
+        "Created" => "Создание",
+        "A-Z" => "А-Я",
+        "Descending" => "По убыванию",
+        "Ascending" => "По возрастанию",
+        "All" => "Все",
+        "Active" => "Активные",
+        "Completed" => "Выполненные",
+        "matching: {} of {}" => "подходит: {} из {}",
+        "Press {} to switch filters" => "{} переключает фильтры",
+        "Any Time" => "За все время",
+        "Today" => "Сегодня",
+        "This Week" => "На этой неделе",
+        "This Month" => "В этом месяце",
+        "Tags:" => "Теги:",
+        "Colors:" => "Цвета:",
+        "Show tasks with this color" => "Показать задачи этого цвета",
+        "{} selected" => "Выбрано: {}",
+        "Complete Selected" => "Выполнить выбранные",
+        "Delete Selected" => "Удалить выбранные",
+        "Apply" => "Применить",
+        "Set this priority for the selected tasks" => "Задать этот приоритет выбранным задачам",
+        "Clear Selection" => "Снять выбор",
+        "Show All" => "Показать все",
+        "Show all tasks again" => "Снова показать все задачи",
+        "No tasks yet — add one above!" => "Задач пока нет — добавьте первую выше!",
+        "No tasks match the current filters." => "Нет задач, подходящих под текущие фильтры.",
+
+        // Строка задачи
+        "Notes" => "Заметки",
+        "Details, links, ideas..." => "Подробности, ссылки, идеи...",
+        "Double-click to edit" => "Дважды щелкните, чтобы изменить",
+        "Double-click to edit, click to select" => "Двойной щелчок — изменить, щелчок — выбрать",
+        "Choose Color" => "Выбрать цвет",
+        "No Color" => "Без цвета",
+        "Select for Bulk Actions" => "Выбрать для групповых действий",
+        "Choose Icon" => "Выбрать значок",
+        "No Icon" => "Без значка",
+        "Show tasks with this tag" => "Показать задачи с этим тегом",
+        "Edit Tags" => "Изменить теги",
+        "Remove Tag" => "Убрать тег",
+        "Add" => "Добавить",
+        "Add Subtask" => "Добавить шаг",
+        "Next step" => "Следующий шаг",
+        "Remove Subtask" => "Удалить шаг",
+        "Low" => "Низкий",
+        "Medium" => "Средний",
+        "High" => "Высокий",
+        "Due date (YYYY-MM-DD)" => "Срок (ГГГГ-ММ-ДД)",
+        "⚠ overdue" => "⚠ просрочено",
+        "Due date presets" => "Быстрый выбор срока",
+        "Snooze until:" => "Отложить до:",
+        "⚠ This date is in the past" => "⚠ Эта дата уже прошла",
+        "Snooze" => "Отложить",
+        "Snoozed until {}" => "Отложено до {}",
+        "Tomorrow" => "Завтра",
+        "Next Week" => "На следующей неделе",
+        "This Weekend" => "В эти выходные",
+        "unknown" => "неизвестно",
+        "Edited today at {}" => "Изменено сегодня в {}",
+        "⚠ duplicate" => "⚠ повтор",
+        "Another task has the same description" => "У другой задачи такое же описание",
+        "done before" => "уже выполнялась",
+        "done before on {}" => "уже выполнялась {}",
+        "A completed task with the same description exists" => "Есть выполненная задача с таким же описанием",
+        "Unpin Task" => "Открепить задачу",
+        "Pin to Top" => "Закрепить вверху",
+        "Move Up" => "Выше",
+        "Move Down" => "Ниже",
+        "Focus on This Task" => "Показать только эту задачу",
+        "Move to Another List" => "Перенести в другой список",
+        "Move" => "Перенести",
+        "Duplicate Task" => "Копировать задачу",
+        "Delete Task" => "Удалить задачу",
+        "Completed ({})" => "Выполненные ({})",
+        "Most Recently Completed First" => "Сначала выполненные последними",
+
+        // Корзина
+        "Clear Completed" => "Удалить выполненные",
+        "Move all completed tasks to the trash" => "Переместить все выполненные задачи в корзину",
+        "Trash ({})" => "Корзина ({})",
+        "The trash is empty" => "Корзина пуста",
+        "deleted {}" => "удалена {}",
+        "Restore" => "Вернуть",
+        "Delete Forever" => "Удалить навсегда",
+        "Tasks are removed from the trash after {} days" => "Задачи удаляются из корзины через {} дней",
+
+        // Архив
+        "Archive Completed" => "Архивировать выполненные",
+        "Move all completed tasks to {}" => "Перенести все выполненные задачи в {}",
+        "View Archive" => "Открыть архив",
+        "Archive ({})" => "Архив ({})",
+        "The archive is empty" => "Архив пуст",
+        "completed {}" => "выполнена {}",
+
+        // Импорт и экспорт
+        "Data" => "Данные",
+        "File:" => "Файл:",
+        "default location" => "расположение по умолчанию",
+        "Import Text" => "Импорт текста",
+        "Add each line of a text or Markdown file as a task" => "Добавить каждую строку текстового или Markdown-файла как задачу",
+        "Import CSV" => "Импорт CSV",
+        "Add the tasks from a CSV file" => "Добавить задачи из CSV-файла",
+        "Import Preview" => "Предпросмотр импорта",
+        "{} tasks in {}" => "Задач: {} в {}",
+        "⚠ {} of them are already in the list" => "⚠ Из них уже есть в списке: {}",
+        "{} malformed rows will be skipped" => "Ошибочных строк будет пропущено: {}",
+        "...and {} more" => "...и еще {}",
+        "Import" => "Импортировать",
+        "Export CSV" => "Экспорт CSV",
+        "Save all tasks to {}" => "Сохранить все задачи в {}",
+        "Export PDF" => "Экспорт PDF",
+        "Save the visible tasks to {}" => "Сохранить видимые задачи в {}",
+        "Copy as Markdown" => "Копировать как Markdown",
+        "Copy all tasks as a Markdown checklist" => "Скопировать все задачи как Markdown-чеклист",
+        "Export Markdown" => "Экспорт Markdown",
+        "Export Journal" => "Экспорт журнала",
+        "Save completed tasks grouped by day to {}" => "Сохранить выполненные задачи по дням в {}",
+        "Export Stats" => "Экспорт статистики",
+        "Save the list statistics to {}" => "Сохранить статистику списка в {}",
+
+        "Restore from Backup" => "Восстановить из копии",
+        "No backups yet" => "Резервных копий пока нет",
+
+        // Диалоги
+        "Confirm" => "Подтвердить",
+        "Cancel" => "Отмена",
+        "Restore Defaults?" => "Сбросить настройки?",
+        "All settings will be reset. Tasks are kept." => "Все настройки будут сброшены. Задачи сохранятся.",
+        "Delete Task?" => "Удалить задачу?",
+        "Delete \"{}\"?" => "Удалить «{}»?",
+        "Clear Completed?" => "Удалить выполненные?",
+        "Remove {} completed task(s)?" => "Удалить выполненные задачи ({})?",
+        "Delete List?" => "Удалить список?",
+        "Delete the list \"{}\"? Its {} task(s) will be moved to the trash." => "Удалить список «{}»? Его задачи ({}) будут перемещены в корзину.",
+        "Delete Selected?" => "Удалить выбранные?",
+        "Delete {} selected task(s)?" => "Удалить выбранные задачи ({})?",
+        "Quit" => "Выход",
+        "A pomodoro is running for \"{}\". Stop it and quit?" => "Для «{}» идет помидор. Остановить его и выйти?",
+        "Stop & Save" => "Остановить и сохранить",
+        "Completion Note" => "Заметка о выполнении",
+        "How did \"{}\" go?" => "Как прошла задача «{}»?",
+        "Save Note" => "Сохранить заметку",
+        "Skip" => "Пропустить",
+        _ => return None,
+    })
+}
@@ -1,261 +1,4942 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 use eframe::egui;
+use i18n::Lang;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use chrono::Local;  // Для получения системного времени
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use chrono::{Local, NaiveDate};  // Для получения системного времени и работы с датами
 
-// Файл, в который будут сохраняться задачи
+mod csv;
+mod fuzzy;
+mod i18n;
+mod markdown;
+mod notify;
+mod pdf;
+mod shortcuts;
+
+// Файл, в который будут сохраняться задачи (по умолчанию — в каталоге настроек)
 const SAVE_FILE: &str = "tasks.json";
 
+// Цвет полосы прогресса: красный ниже PROGRESS_LOW %, желтый до PROGRESS_HIGH %, выше — зеленый
+const PROGRESS_LOW: f32 = 33.0;
+const PROGRESS_HIGH: f32 = 66.0;
+const PROGRESS_COLORS: [egui::Color32; 3] = [
+    egui::Color32::from_rgb(210, 70, 60),
+    egui::Color32::from_rgb(230, 170, 40),
+    egui::Color32::from_rgb(70, 170, 80),
+];
+
+// Каталог приложения внутри системного каталога настроек
+const APP_DIR: &str = "yuko-todo-list";
+
+// Переменная окружения с путем к файлу задач
+const FILE_ENV_VAR: &str = "YUKO_TODO_FILE";
+
+// Журнал действий (рядом с файлом задач) и его предельный размер перед ротацией
+const ACTIVITY_LOG: &str = "activity.log";
+const ACTIVITY_LOG_MAX_BYTES: u64 = 1024 * 1024;
+
+// Архив выполненных задач (рядом с файлом задач); новые задачи дописываются в конец
+const ARCHIVE_FILE: &str = "archive.json";
+
+// Резервные копии файла задач (tasks.json.bak.ГГГГММДД-ЧЧММСС) и сколько из них хранить
+const BACKUP_MARKER: &str = ".bak.";
+const BACKUP_LIMIT: usize = 5;
+
+// Сколько последних дней показывает диаграмма выполненных задач
+const CHART_DAYS: i64 = 7;
+
+// Ширина колонки с описанием задачи
+const DESCRIPTION_WIDTH: f32 = 300.0;
+
+// Длительность помидора и сколько показывается сообщение о его окончании
+const POMODORO: Duration = Duration::from_secs(25 * 60);
+const POMODORO_NOTICE: Duration = Duration::from_secs(10);
+
+// Сколько показывается всплывающее подтверждение
+const TOAST_DURATION: Duration = Duration::from_secs(3);
+
+// Имя списка, в который попадают задачи из файлов без списков
+const DEFAULT_LIST_NAME: &str = "Tasks";
+
+// Срок по умолчанию, после которого невыполненная задача попадает в напоминание
+const STALE_DAYS: u32 = 14;
+
+// Сколько дней удаленные задачи хранятся в корзине
+const TRASH_RETENTION_DAYS: i64 = 30;
+
+// Максимальная глубина истории отмены
+const UNDO_LIMIT: usize = 50;
+
+// Минимальный промежуток между записями файла задач при автосохранении (по умолчанию)
+const SAVE_INTERVAL: Duration = Duration::from_millis(1000);
+
+// Файл для экспорта списка задач в PDF (рядом с файлом задач)
+const PDF_FILE: &str = "tasks.pdf";
+
+// Файлы для экспорта задач в Markdown и CSV (рядом с файлом задач)
+const MARKDOWN_FILE: &str = "tasks.md";
+const JOURNAL_FILE: &str = "journal.md";
+const STATS_FILE: &str = "stats.md";
+const CSV_FILE: &str = "tasks.csv";
+
+// Суффикс файла аварийного сохранения, записываемого при панике
+const RECOVERY_SUFFIX: &str = ".recovery";
+
+// Последнее состояние приложения в JSON для аварийного сохранения
+static RECOVERY_SNAPSHOT: Mutex<Option<String>> = Mutex::new(None);
+
+// Флаг, выставляемый обработчиком SIGINT/SIGTERM
+static SHUTDOWN_REQUESTED: AtomicBool = AtomicBool::new(false);
+
 #[derive(Default, Serialize, Deserialize)]
+#[serde(default)] // Поля, отсутствующие в старых файлах, получают значения по умолчанию
 struct TodoApp {
-    tasks: Vec<Task>,               // Список задач
+    lists: Vec<TaskList>,           // Именованные списки задач (всегда хотя бы один)
+    current_list: usize,            // Открытый список
+    #[serde(rename = "tasks", skip_serializing)]
+    legacy_tasks: Option<Vec<Task>>, // Единый список из старых файлов, заменен на `lists`
     new_task: String,               // Ввод новой задачи
-    selected_task: Option<usize>,   // Редактируемая задача
-    show_completed: bool,           // Флаг отображения выполненных задач
+    selected_task: Option<usize>,   // Задача, поле которой сейчас в фокусе
     search_query: String,           // Поисковый запрос
-    theme: Theme,                   // Текущая тема (светлая/темная)
+    tag_filter: Option<String>,     // Показывать только задачи с этим тегом
+    color_filter: Option<[u8; 3]>,  // Показывать только задачи с этим цветом
+    #[serde(flatten)]
+    settings: Settings,             // Пользовательские настройки
+    scratchpad: String,             // Черновик для заметок, которые еще не стали задачами
+    templates: Vec<String>,         // Шаблоны часто добавляемых задач
+    trash: Vec<TrashedTask>,        // Удаленные задачи, которые можно восстановить
+    window_size: Option<[f32; 2]>,  // Размер окна при последнем запуске
+    window_position: Option<[f32; 2]>, // Положение окна при последнем запуске
+    scroll_offset: f32,             // Прокрутка списка задач при последнем запуске
+    #[serde(skip)]
+    scroll_restored: bool,          // Прокрутка из файла уже применена
+    #[serde(skip)]
+    solo_task: Option<usize>,       // Единственная показываемая задача (остальные скрыты)
+    #[serde(skip)]
+    confirm_reset: bool,            // Открыт диалог сброса настроек
+    #[serde(skip)]
+    pending_delete: Option<usize>,  // Задача, ожидающая подтверждения удаления
+    #[serde(skip)]
+    confirm_clear_completed: bool,  // Открыт диалог удаления выполненных задач
+    #[serde(skip)]
+    marked: HashSet<usize>,         // Задачи, отмеченные для групповых действий
+    #[serde(skip)]
+    confirm_delete_marked: bool,    // Открыт диалог удаления отмеченных задач
+    #[serde(skip)]
+    import_preview: Option<ImportPreview>, // Разобранный файл, ожидающий подтверждения импорта
+    #[serde(skip)]
+    stale_dismissed: bool,          // Напоминание о залежавшихся задачах закрыто до перезапуска
+    #[serde(skip)]
+    stale_filter: bool,             // Показывать только залежавшиеся задачи
+    #[serde(skip)]
+    confirm_quit: bool,             // Открыт диалог выхода при запущенном помидоре
+    #[serde(skip)]
+    bulk_priority: Priority,        // Приоритет, выбранный для отмеченных задач
+    #[serde(skip)]
+    notes_open: HashSet<usize>,     // Задачи с раскрытыми заметками
+    #[serde(skip)]
+    active_timer: Option<(usize, Instant)>, // Задача с запущенным помидором и время запуска
+    #[serde(skip)]
+    finished_pomodoro: Option<(String, Instant)>, // Задача, чей помидор только что закончился
+    #[serde(skip)]
+    toast: Option<(String, Instant)>, // Всплывающее подтверждение и время его появления
+    #[serde(skip)]
+    snooze_input: String,           // Дата в поле "Отложить до"
+    #[serde(skip)]
+    list_rename: Option<String>,    // Новое имя открытого списка, пока оно редактируется
+    #[serde(skip)]
+    confirm_delete_list: bool,      // Открыт диалог удаления открытого списка
+    #[serde(skip)]
+    archive_view: Option<Vec<ArchivedTask>>, // Открытый просмотр архива
+    #[serde(skip)]
+    focus_description: Option<usize>, // Описание, которое получит фокус в следующем кадре
+    #[serde(skip)]
+    confirm_duplicate: bool,        // Новая задача повторяет существующую, ждем подтверждения
+    #[serde(skip)]
+    save_path: PathBuf,             // Путь к файлу задач
+    #[serde(skip)]
+    data_path: String,              // Путь к файлу для импорта и экспорта
+    #[serde(skip)]
+    last_error: Option<String>,     // Последняя ошибка работы с файлами
+    #[serde(skip)]
+    save_error: Option<String>,     // Ошибка последнего сохранения задач
+    #[serde(skip)]
+    corrupt_file: Option<PathBuf>,  // Куда переименован нечитаемый файл задач при запуске
+    #[serde(skip)]
+    dirty: bool,                    // Есть несохраненные изменения
+    #[serde(skip)]
+    snapshot_stale: bool,           // Снимок для аварийного сохранения устарел
+    #[serde(skip)]
+    last_save: Option<Instant>,     // Время последней записи файла задач
+    #[serde(skip)]
+    description_edited: bool,       // Описание в фокусе было изменено
+    #[serde(skip)]
+    completion_prompt: Option<(usize, String)>, // Задача, ожидающая заметки о выполнении
+    #[serde(skip)]
+    move_destination: String,       // Путь к файлу, в который переносится задача
+    #[serde(skip)]
+    tag_input: String,              // Ввод нового тега для задачи
+    #[serde(skip)]
+    subtask_input: String,          // Ввод нового шага задачи
+    #[serde(skip)]
+    recovery_available: bool,       // Найден файл аварийного сохранения
+    #[serde(skip)]
+    undo_stack: Vec<Snapshot>,      // Состояния списка для отмены (Ctrl+Z)
+    #[serde(skip)]
+    redo_stack: Vec<Snapshot>,      // Отмененные состояния для повтора (Ctrl+Y)
+    #[serde(skip)]
+    edit_snapshot: Option<Vec<Task>>, // Открытый список до начала редактирования описания
+    #[serde(skip)]
+    field_undo: Option<(usize, TaskField)>, // Поле, состояние до правки которого уже в истории отмены
+    #[serde(skip)]
+    subtask_snapshot: Option<Vec<Task>>, // Открытый список до начала правки шага задачи
 }
 
+// Настройки, которые можно сбросить к значениям по умолчанию, не трогая задачи
 #[derive(Default, Serialize, Deserialize)]
-struct Task {
-    description: String, // Описание задачи
-    completed: bool,     // Статус выполнения задачи
+#[serde(default)]
+struct Settings {
+    status_filter: StatusFilter,   // Какие задачи показывать: все, активные, выполненные или на сегодня
+    focus_today: bool,             // Открывать приложение с фильтром "Сегодня"
+    completed_within: CompletedWithin, // Период выполнения для фильтра "Выполненные"
+    #[serde(rename = "show_completed", skip_serializing)]
+    legacy_show_completed: Option<bool>, // Флажок из старых файлов, заменен на `status_filter`
+    theme: Theme,                  // Текущая тема (темная/светлая/контрастная/авто)
+    progress_style: ProgressStyle, // Вид индикатора прогресса
+    activity_log: bool,            // Запись действий в журнал
+    note_on_complete: bool,        // Спрашивать заметку при выполнении задачи
+    weight_subtasks: bool,         // Учитывать выполненные шаги в прогрессе
+    accent: Option<[u8; 3]>,       // Цвет акцента (RGB); без него — цвет темы egui
+    max_task_len: usize,           // Максимальная длина описания в символах (0 — без ограничения)
+    language: Lang,                // Язык интерфейса
+    time_format: TimeFormat,       // Формат часов в углу окна
+    fuzzy_search: bool,            // Нечеткий поиск вместо поиска подстроки
+    global_search: bool,           // Один поисковый запрос для всех списков вместо своего у каждого
+    mute_notifications: bool,      // Не показывать уведомления о просроченных задачах
+    manual_save: bool,             // Записывать файл задач только по кнопке (и при выходе)
+    save_interval: SaveInterval,   // Промежуток между записями при автосохранении
+    ui_scale: UiScale,             // Масштаб интерфейса относительно системного
+    sort_mode: SortMode,           // Порядок сортировки списка
+    sort_descending: bool,         // Сортировка по убыванию
+    completed_by_time: bool,       // Выполненные задачи от последней выполненной к первой
+    day_rollover_hour: u8,         // Час, с которого начинается новый день (0 — полночь)
+    stale_days: StaleDays,         // Возраст, с которого невыполненная задача считается залежавшейся
 }
 
-#[derive(Serialize, Deserialize, PartialEq)]
-enum Theme {
-    Dark,
-    Light,
+impl Settings {
+    // Перенос флажка `show_completed` из старых файлов в фильтр по статусу
+    fn migrate(&mut self) {
+        if let Some(show_completed) = self.legacy_show_completed.take() {
+            self.status_filter = if show_completed { StatusFilter::All } else { StatusFilter::Active };
+        }
+    }
 }
 
-impl Default for Theme {
+// Формат часов в синтаксисе strftime из chrono
+#[derive(Clone, Serialize, Deserialize, PartialEq)]
+#[serde(transparent)]
+struct TimeFormat(String);
+
+impl Default for TimeFormat {
     fn default() -> Self {
-        Theme::Dark
+        TimeFormat(TimeFormat::PRESETS[0].1.to_string())
     }
 }
 
-impl TodoApp {
-    // Метод для загрузки задач из файла
-    fn load_tasks() -> Self {
-        if let Ok(data) = fs::read_to_string(SAVE_FILE) {
-            if let Ok(app) = serde_json::from_str::<TodoApp>(&data) {
-                return app;
-            }
+impl TimeFormat {
+    // Готовые форматы: 24 часа, 12 часов с AM/PM, дата и время
+    const PRESETS: [(&'static str, &'static str); 3] = [
+        ("24-hour", "%H:%M:%S"),
+        ("12-hour", "%I:%M:%S %p"),
+        ("With Date", "%Y-%m-%d %H:%M:%S"),
+    ];
+
+    // Можно ли отформатировать время этой строкой
+    fn is_valid(&self) -> bool {
+        !self.0.trim().is_empty()
+            && chrono::format::StrftimeItems::new(&self.0).all(|item| item != chrono::format::Item::Error)
+    }
+
+    // Формат для вывода; ошибочная строка заменяется форматом по умолчанию
+    fn valid(&self) -> &str {
+        if self.is_valid() {
+            &self.0
+        } else {
+            TimeFormat::PRESETS[0].1
         }
-        TodoApp::default() // Если чтение не удалось, возвращаем пустой список задач
     }
+}
 
-    // Метод для сохранения задач в файл
-    fn save_tasks(&self) {
-        if let Ok(data) = serde_json::to_string_pretty(self) {
-            let _ = fs::write(SAVE_FILE, data); // Игнорируем ошибку при записи
+// Через сколько дней невыполненная задача считается залежавшейся
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(transparent)]
+struct StaleDays(u32);
+
+impl Default for StaleDays {
+    fn default() -> Self {
+        StaleDays(STALE_DAYS)
+    }
+}
+
+impl StaleDays {
+    const RANGE: std::ops::RangeInclusive<u32> = 1..=365;
+
+    fn get(self) -> u32 {
+        self.0.clamp(*Self::RANGE.start(), *Self::RANGE.end())
+    }
+}
+
+// Промежуток автосохранения в секундах; значение из файла приводится к допустимому диапазону
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(transparent)]
+struct SaveInterval(u64);
+
+impl Default for SaveInterval {
+    fn default() -> Self {
+        SaveInterval(SAVE_INTERVAL.as_secs())
+    }
+}
+
+impl SaveInterval {
+    const RANGE: std::ops::RangeInclusive<u64> = 1..=30;
+
+    fn get(self) -> Duration {
+        Duration::from_secs(self.0.clamp(*Self::RANGE.start(), *Self::RANGE.end()))
+    }
+}
+
+// Масштаб интерфейса; значение из файла приводится к допустимому диапазону
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(transparent)]
+struct UiScale(f32);
+
+impl Default for UiScale {
+    fn default() -> Self {
+        UiScale(1.0)
+    }
+}
+
+impl UiScale {
+    const RANGE: std::ops::RangeInclusive<f32> = 0.75..=2.0;
+    const STEP: f32 = 0.1;
+
+    fn get(self) -> f32 {
+        self.0.clamp(*Self::RANGE.start(), *Self::RANGE.end())
+    }
+
+    // Изменение масштаба на `steps` шагов в пределах диапазона
+    fn bump(&mut self, steps: f32) {
+        self.0 = (self.get() + steps * Self::STEP).clamp(*Self::RANGE.start(), *Self::RANGE.end());
+    }
+}
+
+#[derive(Default, Clone, Copy, Serialize, Deserialize, PartialEq)]
+enum StatusFilter {
+    All,
+    #[default]
+    Active,
+    Completed,
+    Today,
+}
+
+impl StatusFilter {
+    const ALL: [StatusFilter; 4] = [StatusFilter::All, StatusFilter::Active, StatusFilter::Completed, StatusFilter::Today];
+
+    // Название фильтра
+    fn label(self) -> &'static str {
+        match self {
+            StatusFilter::All => "All",
+            StatusFilter::Active => "Active",
+            StatusFilter::Completed => "Completed",
+            StatusFilter::Today => "Today",
         }
     }
 
-    // Подсчет количества выполненных задач
-    fn completed_tasks(&self) -> usize {
-        self.tasks.iter().filter(|task| task.completed).count()
+    // Следующий фильтр по кругу
+    fn next(self) -> StatusFilter {
+        let index = StatusFilter::ALL.iter().position(|&filter| filter == self).unwrap_or(0);
+        StatusFilter::ALL[(index + 1) % StatusFilter::ALL.len()]
     }
 
-    // Процент выполнения задач
-    fn progress(&self) -> f32 {
-        if self.tasks.is_empty() {
-            0.0
-        } else {
-            (self.completed_tasks() as f32 / self.tasks.len() as f32) * 100.0
+    // Проходит ли задача через фильтр; "Сегодня" — невыполненные задачи со сроком сегодня или раньше
+    fn matches(self, task: &Task, today: NaiveDate) -> bool {
+        match self {
+            StatusFilter::All => true,
+            StatusFilter::Active => !task.completed,
+            StatusFilter::Completed => task.completed,
+            StatusFilter::Today => !task.completed && task.due().is_some_and(|due| due <= today),
         }
     }
+}
 
-    // Переключение темы
-    fn toggle_theme(&mut self) {
-        self.theme = match self.theme {
-            Theme::Dark => Theme::Light,
-            Theme::Light => Theme::Dark,
-        };
-        self.save_tasks();
+// Когда выполнена задача: сегодня, на этой неделе (с понедельника) или в этом месяце
+#[derive(Default, Clone, Copy, Serialize, Deserialize, PartialEq)]
+enum CompletedWithin {
+    #[default]
+    AnyTime,
+    Today,
+    ThisWeek,
+    ThisMonth,
+}
+
+impl CompletedWithin {
+    const ALL: [CompletedWithin; 4] = [
+        CompletedWithin::AnyTime,
+        CompletedWithin::Today,
+        CompletedWithin::ThisWeek,
+        CompletedWithin::ThisMonth,
+    ];
+
+    // Название периода
+    fn label(self) -> &'static str {
+        match self {
+            CompletedWithin::AnyTime => "Any Time",
+            CompletedWithin::Today => "Today",
+            CompletedWithin::ThisWeek => "This Week",
+            CompletedWithin::ThisMonth => "This Month",
+        }
     }
 
-    // Получение текущего времени в формате строки
-    fn current_time() -> String {
-        let now = Local::now();
-        now.format("%H:%M:%S").to_string()  // Текущее время в формате ЧЧ:ММ:СС
+    // Попадает ли день выполнения в период, отсчитанный от `today`
+    fn contains(self, day: NaiveDate, today: NaiveDate) -> bool {
+        use chrono::Datelike;
+        match self {
+            CompletedWithin::AnyTime => true,
+            CompletedWithin::Today => day == today,
+            CompletedWithin::ThisWeek => day.iso_week() == today.iso_week(),
+            CompletedWithin::ThisMonth => day.year() == today.year() && day.month() == today.month(),
+        }
     }
 
-    // Фильтрация задач по поисковому запросу
-    fn filtered_tasks(&self) -> Vec<(usize, &Task)> {
-        self.tasks.iter()
-            .enumerate()
-            .filter(|(_, task)| {
-                task.description
-                    .to_lowercase()
-                    .contains(&self.search_query.to_lowercase())
-            })
-            .collect()
+    // Проходит ли задача через фильтр; задача без времени выполнения подходит только под "AnyTime"
+    fn matches(self, task: &Task, today: NaiveDate, rollover_hour: u8) -> bool {
+        self == CompletedWithin::AnyTime
+            || task.completed_day(rollover_hour).is_some_and(|day| self.contains(day, today))
     }
 }
 
-impl eframe::App for TodoApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // Установка темы интерфейса
-        ctx.set_style(egui::Style {
-            visuals: match self.theme {
-                Theme::Dark => egui::Visuals::dark(),
-                Theme::Light => egui::Visuals::light(),
-            },
-            ..Default::default()
-        });
+#[derive(Default, Clone, Copy, Serialize, Deserialize, PartialEq)]
+enum SortMode {
+    #[default]
+    Manual,
+    Priority,
+    Created,
+    Alphabetical,
+}
 
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.heading("To-Do List");
-            ui.separator();
+impl SortMode {
+    const ALL: [SortMode; 4] = [SortMode::Manual, SortMode::Priority, SortMode::Created, SortMode::Alphabetical];
 
-            // Отображение текущего времени в правом верхнем углу
-            ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
-                ui.label(TodoApp::current_time());
-            });
+    // Название режима сортировки
+    fn label(self) -> &'static str {
+        match self {
+            SortMode::Manual => "Manual",
+            SortMode::Priority => "Priority",
+            SortMode::Created => "Created",
+            SortMode::Alphabetical => "A-Z",
+        }
+    }
+}
 
-            // Кнопка для смены темы
-            if ui.button("Toggle Theme").clicked() {
-                self.toggle_theme();
-            }
+#[derive(Default, Serialize, Deserialize, PartialEq)]
+enum ProgressStyle {
+    #[default]
+    Bar,
+    Ring,
+}
 
-            ui.horizontal(|ui| {
-                // Полоса прогресса с анимацией
-                let progress = self.progress() / 100.0;
-                ui.label(format!("Progress: {:.2}%", self.progress()));
-                ui.add(egui::ProgressBar::new(progress)
-                    .animate(true)  // Включаем анимацию
-                    .desired_width(300.0)
-                );
-            });
+#[derive(Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct Task {
+    description: String,             // Описание задачи
+    completed: bool,                 // Статус выполнения задачи
+    icon: Option<String>,            // Значок задачи
+    completion_note: Option<String>, // Заметка, оставленная при выполнении
+    updated_at: Option<String>,      // Время последнего изменения (RFC 3339)
+    created_at: String,              // Время создания (RFC 3339), пусто для старых задач
+    completed_at: Option<String>,    // Время выполнения (RFC 3339)
+    priority: Priority,              // Приоритет задачи
+    due_date: Option<String>,        // Срок выполнения в формате ГГГГ-ММ-ДД
+    tags: Vec<String>,               // Теги задачи (например, @home, @work)
+    subtasks: Vec<Subtask>,          // Шаги задачи
+    pinned: bool,                    // Закрепленная задача всегда выше остальных
+    notes: String,                   // Подробные заметки к задаче
+    color: Option<[u8; 3]>,          // Цвет метки задачи (RGB)
+    pomodoros: u32,                  // Сколько помидоров потрачено на задачу
+    focus_secs: u64,                 // Сколько секунд отработано по таймеру, включая прерванные помидоры
+    notified: bool,                  // Уведомление о просрочке уже показано
+}
 
-            ui.separator();
+// Задача в архиве: список, из которого она пришла, и время архивации
+#[derive(Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct ArchivedTask {
+    task: Task,
+    list: String,
+    archived_at: String, // Время архивации (RFC 3339)
+}
 
-            // Поле для ввода новой задачи
-            ui.vertical(|ui| {
-                ui.add(egui::TextEdit::multiline(&mut self.new_task)
-                    .hint_text("Enter a new task...")  // Подсказка
-                    .desired_rows(3)                 // Количество строк
-                    .desired_width(300.0)            // Ширина поля
-                );
+// Именованный список задач
+#[derive(Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct TaskList {
+    name: String,
+    tasks: Vec<Task>,
+    search_query: String, // Поисковый запрос списка, пока открыт другой (при отдельном поиске)
+}
 
-                // Кнопка добавления задачи
-                if ui.button("Add Task").clicked() {
-                    if !self.new_task.is_empty() {
-                        self.tasks.push(Task {
-                            description: self.new_task.clone(),
-                            completed: false,
-                        });
-                        self.new_task.clear();
-                        self.save_tasks(); // Автосохранение
-                    }
-                }
-            });
+impl TaskList {
+    fn new(name: String) -> Self {
+        TaskList { name, ..Default::default() }
+    }
+}
 
-            ui.separator();
+// Задача в корзине вместе со временем удаления
+#[derive(Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct TrashedTask {
+    task: Task,
+    deleted_at: String, // Время удаления (RFC 3339)
+}
 
-            // Поисковая строка
-            ui.horizontal(|ui| {
-                ui.label("Search:");
-                ui.text_edit_singleline(&mut self.search_query);
-            });
+// Состояние для отмены: все списки, открытый список и корзина меняются вместе
+type Snapshot = (Vec<TaskList>, usize, Vec<TrashedTask>);
 
-            // Флажок отображения выполненных задач
-            ui.checkbox(&mut self.show_completed, "Show Completed Tasks");
+// Формат импортируемого файла
+#[derive(Clone, Copy)]
+enum ImportFormat {
+    Text, // Текст или Markdown-чеклист
+    Csv,
+}
 
-            // Список задач с фильтрацией
-            egui::ScrollArea::vertical().show(ui, |ui| {
-                let mut to_remove = Vec::new();
-                let mut edit_task = None;
+// Разобранный файл импорта, который показывается перед добавлением задач
+struct ImportPreview {
+    path: PathBuf,
+    tasks: Vec<Task>,
+    duplicates: usize,   // Задачи, которые уже есть в списке или повторяются в файле
+    errors: Vec<String>, // Ошибочные строки CSV, которые будут пропущены
+}
 
-                // Индексы задач для редактирования или удаления
-                let task_indices: Vec<usize> = self.filtered_tasks()
-                    .iter()
-                    .filter(|(_, task)| self.show_completed || !task.completed)
-                    .map(|(i, _)| *i)
-                    .collect();
+impl ImportPreview {
+    // Сколько первых задач показывать в предпросмотре
+    const SAMPLE_ROWS: usize = 5;
 
-                // Отображение задач
-                for i in task_indices {
-                    let task = &mut self.tasks[i];
-                    ui.horizontal(|ui| {
-                        // Чекбокс выполнения задачи
-                        let checkbox_response = ui.checkbox(&mut task.completed, "");
-                        checkbox_response.changed(); // Отслеживаем изменения
-
-                        // Если задача редактируется
-                        if self.selected_task.map_or(false, |selected| selected == i) {
-                            ui.add(egui::TextEdit::multiline(&mut task.description)
-                                .desired_rows(3)
-                                .desired_width(300.0),
-                            );
-                        } else {
-                            let style = if task.completed {
-                                egui::TextEdit::multiline(&mut task.description)
-                                    .interactive(false)
-                                    .desired_width(300.0)
-                                    .text_color(egui::Color32::from_gray(120))  // Серый цвет для выполненных задач
-                            } else {
-                                egui::TextEdit::multiline(&mut task.description)
-                                    .interactive(false)
-                                    .desired_width(300.0)
-                            };
-                            ui.add(style);
-                        }
+    fn sample(&self) -> &[Task] {
+        &self.tasks[..self.tasks.len().min(ImportPreview::SAMPLE_ROWS)]
+    }
+}
 
-                        // Кнопка "Edit"
-                        if ui.button("✏️").on_hover_text("Edit Task").clicked() {
-                            edit_task = Some(i);
-                        }
+// Шаг задачи со своим флажком выполнения
+#[derive(Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct Subtask {
+    description: String,
+    completed: bool,
+}
 
-                        // Кнопка "Delete"
-                        if ui.button("🗑").on_hover_text("Delete Task").clicked() {
-                            to_remove.push(i);
-                        }
-                    });
-                }
+#[derive(Default, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+enum Priority {
+    Low,
+    #[default]
+    Medium,
+    High,
+}
 
-                // Удаление задач
-                for index in to_remove.iter().rev() {
-                    self.tasks.remove(*index);
-                    self.save_tasks(); // Автосохранение
-                }
+impl Priority {
+    const ALL: [Priority; 3] = [Priority::Low, Priority::Medium, Priority::High];
 
-                // Режим редактирования задачи
-                if let Some(task_index) = edit_task {
-                    self.selected_task = Some(task_index);
-                }
-            });
+    // Название приоритета
+    fn label(self) -> &'static str {
+        match self {
+            Priority::Low => "Low",
+            Priority::Medium => "Medium",
+            Priority::High => "High",
+        }
+    }
+
+    // Цвет индикатора приоритета
+    fn color(self) -> egui::Color32 {
+        match self {
+            Priority::Low => egui::Color32::from_rgb(80, 180, 80),
+            Priority::Medium => egui::Color32::from_rgb(230, 190, 40),
+            Priority::High => egui::Color32::from_rgb(220, 60, 60),
+        }
+    }
+}
+
+// Быстрый выбор срока выполнения
+#[derive(Clone, Copy)]
+enum DuePreset {
+    Today,
+    Tomorrow,
+    NextWeek,
+    ThisWeekend,
+}
+
+impl DuePreset {
+    const ALL: [DuePreset; 4] = [DuePreset::Today, DuePreset::Tomorrow, DuePreset::NextWeek, DuePreset::ThisWeekend];
+
+    // Название варианта
+    fn label(self) -> &'static str {
+        match self {
+            DuePreset::Today => "Today",
+            DuePreset::Tomorrow => "Tomorrow",
+            DuePreset::NextWeek => "Next Week",
+            DuePreset::ThisWeekend => "This Weekend",
+        }
+    }
+
+    // Срок относительно `today`: следующая неделя начинается с ближайшего понедельника,
+    // выходные — с ближайшей субботы (в субботу и воскресенье это сам день)
+    fn date(self, today: NaiveDate) -> NaiveDate {
+        use chrono::Datelike;
+        let weekday = today.weekday().num_days_from_monday() as i64;
+        match self {
+            DuePreset::Today => today,
+            DuePreset::Tomorrow => today + chrono::Duration::days(1),
+            DuePreset::NextWeek => today + chrono::Duration::days(7 - weekday),
+            DuePreset::ThisWeekend => today + chrono::Duration::days((5 - weekday).max(0)),
+        }
+    }
+}
+
+// Поле задачи, правки которого объединяются в один шаг отмены
+#[derive(Clone, Copy, PartialEq)]
+enum TaskField {
+    Due,
+    Notes,
+    Color,
+}
+
+// Готовые цвета меток задач
+const LABEL_COLORS: [[u8; 3]; 8] = [
+    [220, 60, 60], [240, 150, 40], [230, 200, 40], [80, 180, 80],
+    [40, 170, 200], [60, 110, 220], [150, 90, 210], [140, 140, 140],
+];
+
+// Значки, доступные в меню выбора
+const TASK_ICONS: [&str; 16] = [
+    "⭐", "🔥", "💡", "📌", "📅", "📞", "📧", "🏠",
+    "💼", "🛒", "💰", "🎯", "🐛", "📚", "✈", "❤",
+];
+
+impl Task {
+    // Новая невыполненная задача с заданным описанием
+    fn new(description: String) -> Self {
+        Task {
+            description,
+            created_at: Local::now().to_rfc3339(),
+            ..Default::default()
+        }
+    }
+
+    // Доля выполненной работы: выполненная задача — 1, иначе доля выполненных шагов
+    fn completion(&self) -> f32 {
+        if self.completed || self.subtasks.is_empty() {
+            return if self.completed { 1.0 } else { 0.0 };
+        }
+        let done = self.subtasks.iter().filter(|subtask| subtask.completed).count();
+        done as f32 / self.subtasks.len() as f32
+    }
+
+    // Приведение статуса задачи к статусу шагов: все шаги выполнены — выполнена и задача.
+    // Возвращает новый статус, если он изменился.
+    fn sync_with_subtasks(&mut self) -> Option<bool> {
+        if self.subtasks.is_empty() {
+            return None;
+        }
+        let all_done = self.subtasks.iter().all(|subtask| subtask.completed);
+        if all_done == self.completed {
+            return None;
+        }
+        self.completed = all_done;
+        self.completed_at = all_done.then(|| Local::now().to_rfc3339());
+        if !all_done {
+            self.completion_note = None;
+        }
+        Some(all_done)
+    }
+
+    // Отметка о последнем изменении задачи
+    fn touch(&mut self) {
+        self.updated_at = Some(Local::now().to_rfc3339());
+    }
 
-            // Кнопка для удаления выполненных задач
-            if ui.button("Clear Completed").on_hover_text("Remove all completed tasks").clicked() {
-                self.tasks.retain(|task| !task.completed);
-                self.save_tasks(); // Автосохранение
+    // Время изменения, если задача менялась сегодня (время создания изменением не считается)
+    fn edited_today(&self, today: NaiveDate, rollover_hour: u8) -> Option<String> {
+        let updated = self.updated_at.as_deref().filter(|updated| *updated != self.created_at)?;
+        let updated = parse_timestamp(updated)?;
+        (effective_date(updated, rollover_hour) == today).then(|| updated.format("%H:%M").to_string())
+    }
+
+    // Невыполненная задача, созданная больше `days` дней назад; задача без времени создания не в счет
+    fn is_stale(&self, now: chrono::DateTime<Local>, days: u32) -> bool {
+        !self.completed
+            && parse_timestamp(&self.created_at).is_some_and(|created| now - created > chrono::Duration::days(days as i64))
+    }
+
+    // День выполнения с учетом часа смены дня
+    fn completed_day(&self, rollover_hour: u8) -> Option<NaiveDate> {
+        let completed = parse_timestamp(self.completed_at.as_deref()?)?;
+        Some(effective_date(completed, rollover_hour))
+    }
+
+    // Срок выполнения; некорректная дата считается отсутствующей
+    fn due(&self) -> Option<NaiveDate> {
+        parse_due_date(self.due_date.as_deref()?)
+    }
+
+    // Просрочена ли невыполненная задача
+    fn is_overdue(&self, today: NaiveDate) -> bool {
+        !self.completed && self.due().is_some_and(|due| due < today)
+    }
+
+    // Время выполнения для отображения в списке
+    fn completed_label(&self) -> Option<String> {
+        let completed = parse_timestamp(self.completed_at.as_deref()?)?;
+        Some(completed.format("%Y-%m-%d %H:%M").to_string())
+    }
+
+    // Время для порядка выполненных задач: время выполнения, у старых задач — время создания
+    fn completion_time(&self) -> Option<chrono::DateTime<Local>> {
+        self.completed_at.as_deref().and_then(parse_timestamp).or_else(|| parse_timestamp(&self.created_at))
+    }
+
+    // Время создания для отображения в списке; у старых задач его нет
+    fn created_label(&self) -> Option<String> {
+        parse_timestamp(&self.created_at).map(|created| created.format("%Y-%m-%d %H:%M").to_string())
+    }
+}
+
+#[derive(Default, Serialize, Deserialize, PartialEq)]
+enum Theme {
+    #[default]
+    Dark,
+    Light,
+    HighContrast,
+    Auto, // Светлая или темная в зависимости от настроек системы
+}
+
+impl TodoApp {
+    // Метод для загрузки задач из файла
+    // В строгом режиме отсутствие файла считается ошибкой, а не пустым списком
+    fn load_tasks(save_path: PathBuf, strict: bool) -> Self {
+        if strict && !save_path.exists() {
+            eprintln!("Task file '{}' does not exist (started with --strict)", save_path.display());
+            std::process::exit(1);
+        }
+        // Если основной файл поврежден или отсутствует, пробуем незавершенную запись
+        let loaded = read_task_file(&save_path);
+        // Резервная копия делается один раз за запуск и только с читаемого файла
+        let mut file_error = loaded.as_ref()
+            .and_then(|_| create_backup(&save_path).err())
+            .map(|error| format!("Failed to back up {}: {}", save_path.display(), error));
+        // Нечитаемый файл переименовывается, чтобы следующее сохранение его не затерло
+        let mut corrupt_file = None;
+        if loaded.is_none() && save_path.exists() {
+            let corrupt_path = with_suffix(&save_path, &format!(".corrupt-{}", Local::now().format("%Y%m%d-%H%M%S")));
+            match fs::rename(&save_path, &corrupt_path) {
+                Ok(()) => corrupt_file = Some(corrupt_path),
+                Err(error) => file_error = Some(format!("Failed to preserve unreadable {}: {}", save_path.display(), error)),
             }
+        }
+        let mut app = loaded
+            .or_else(|| read_task_file(&with_suffix(&save_path, ".tmp")))
+            .unwrap_or_default(); // Если чтение не удалось, возвращаем пустой список задач
+        app.migrate();
+        app.save_path = save_path;
+        app.last_error = file_error;
+        app.corrupt_file = corrupt_file;
+        app.purge_old_trash();
+        app.apply_launch_filter();
+        app
+    }
 
-            // Кнопка для сохранения изменений
-            if self.selected_task.is_some() {
-                if ui.button("Save Changes").on_hover_text("Save task changes").clicked() {
-                    self.selected_task = None;
-                    self.save_tasks(); // Автосохранение
-                }
+    // Фильтр при запуске: с настройкой "Сегодня" открываются задачи на сегодня и просроченные,
+    // а если таких нет — все задачи
+    fn apply_launch_filter(&mut self) {
+        if !self.settings.focus_today {
+            return;
+        }
+        let today = self.today();
+        let has_agenda = self.tasks().iter().any(|task| StatusFilter::Today.matches(task, today));
+        self.settings.status_filter = if has_agenda { StatusFilter::Today } else { StatusFilter::All };
+    }
+
+    // Перенос данных из старых файлов; после него всегда есть хотя бы один список
+    fn migrate(&mut self) {
+        self.settings.migrate();
+        if let Some(tasks) = self.legacy_tasks.take() {
+            if self.lists.is_empty() {
+                self.lists.push(TaskList { name: DEFAULT_LIST_NAME.to_string(), tasks, ..Default::default() });
             }
-        });
+        }
+        if self.lists.is_empty() {
+            self.lists.push(TaskList::new(DEFAULT_LIST_NAME.to_string()));
+        }
+        self.current_list = self.current_list.min(self.lists.len() - 1);
+        // У задач из старых файлов время изменения не записано: берется время создания
+        for task in self.lists.iter_mut().flat_map(|list| list.tasks.iter_mut()) {
+            if task.updated_at.is_none() && !task.created_at.is_empty() {
+                task.updated_at = Some(task.created_at.clone());
+            }
+        }
+    }
 
-        // Перерисовка интерфейса для обновления времени
-        ctx.request_repaint();
+    // Задачи открытого списка
+    fn tasks(&self) -> &Vec<Task> {
+        &self.lists[self.current_list].tasks
     }
-}
 
-fn main() {
-    let options = eframe::NativeOptions::default();
-    let app = TodoApp::load_tasks(); // Загрузка задач при старте приложения
+    fn tasks_mut(&mut self) -> &mut Vec<Task> {
+        &mut self.lists[self.current_list].tasks
+    }
 
-    eframe::run_native(
-        "To-Do List",
-        options,
-        Box::new(|_cc| Ok(Box::new(app))),
-    )
-    .unwrap();
+    // Переход к другому списку; отметки и выбор задач относятся к прежнему списку
+    fn switch_list(&mut self, index: usize) {
+        if index == self.current_list {
+            return;
+        }
+        // Без общего поиска запрос уходит в прежний список, а открытый список возвращает свой
+        if !self.settings.global_search {
+            self.lists[self.current_list].search_query = std::mem::take(&mut self.search_query);
+            self.search_query = std::mem::take(&mut self.lists[index].search_query);
+        }
+        self.current_list = index;
+        self.reset_task_state();
+        self.list_rename = None;
+        // Теги и цвета у каждого списка свои
+        self.tag_filter = None;
+        self.color_filter = None;
+        self.autosave();
+    }
+
+    // Новый пустой список, который сразу открывается для переименования
+    fn add_list(&mut self) {
+        let name = format!("List {}", self.lists.len() + 1);
+        self.log_action(&format!("add list: {}", name));
+        self.record_undo();
+        self.lists.push(TaskList::new(name.clone()));
+        self.switch_list(self.lists.len() - 1);
+        self.list_rename = Some(name);
+    }
+
+    // Переименование открытого списка; пустое имя игнорируется
+    fn rename_list(&mut self, name: &str) {
+        let name = name.trim();
+        let list = &self.lists[self.current_list];
+        if name.is_empty() || name == list.name {
+            return;
+        }
+        let entry = format!("rename list: {} -> {}", list.name, name);
+        self.log_action(&entry);
+        self.record_undo();
+        self.lists[self.current_list].name = name.to_string();
+        self.autosave();
+    }
+
+    // Удаление открытого списка; его задачи попадают в корзину. Последний список не удаляется.
+    fn delete_list(&mut self) {
+        if self.lists.len() < 2 {
+            return;
+        }
+        self.record_undo();
+        let list = self.lists.remove(self.current_list);
+        self.log_action(&format!("delete list: {}", list.name));
+        let deleted_at = Local::now().to_rfc3339();
+        self.trash.extend(list.tasks.into_iter().map(|task| TrashedTask { task, deleted_at: deleted_at.clone() }));
+        // Индекс открытого списка больше не действителен, поэтому переход выполняется вручную
+        self.current_list = self.current_list.min(self.lists.len() - 1);
+        if !self.settings.global_search {
+            self.search_query = std::mem::take(&mut self.lists[self.current_list].search_query);
+        }
+        self.reset_task_state();
+        self.tag_filter = None;
+        self.color_filter = None;
+        self.autosave();
+    }
+
+    // Файл рядом с файлом задач (журнал, экспорт)
+    fn sibling_path(&self, name: &str) -> PathBuf {
+        self.save_path.with_file_name(name)
+    }
+
+    // Путь из раздела "Data" или файл по умолчанию рядом с файлом задач
+    fn data_path_or(&self, default_name: &str) -> PathBuf {
+        match self.data_path.trim() {
+            "" => self.sibling_path(default_name),
+            path => PathBuf::from(path),
+        }
+    }
+
+    // Каталог, в котором лежит файл задач
+    fn task_dir(&self) -> PathBuf {
+        match self.save_path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => PathBuf::from("."),
+        }
+    }
+
+    // Метод для сохранения задач в файл
+    fn save_tasks(&self) -> std::io::Result<()> {
+        let data = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        fs::create_dir_all(self.task_dir())?; // Каталог настроек может еще не существовать
+        write_atomically(&self.save_path, data.as_bytes())
+    }
+
+    // Автосохранение: изменения помечаются и записываются не чаще раза в заданный промежуток
+    fn autosave(&mut self) {
+        self.dirty = true;
+        self.snapshot_stale = true;
+    }
+
+    // Запись помеченных изменений, если с прошлого сохранения прошло достаточно времени.
+    // При ручном сохранении изменения ждут кнопки "Save Now" или выхода из приложения.
+    fn flush_if_due(&mut self) {
+        let interval = self.settings.save_interval.get();
+        let due = self.last_save.is_none_or(|time| time.elapsed() >= interval);
+        if self.dirty && due && !self.settings.manual_save {
+            self.flush_save();
+        }
+    }
+
+    // Немедленное сохранение: ошибка запоминается и показывается до следующего успешного сохранения.
+    // При ошибке изменения остаются помеченными, и запись повторится через промежуток автосохранения.
+    fn flush_save(&mut self) {
+        self.last_save = Some(Instant::now());
+        self.save_error = self.save_tasks()
+            .err()
+            .map(|error| format!("Failed to save {}: {}", self.save_path.display(), error));
+        self.dirty = self.save_error.is_some();
+    }
+
+    // Сохранение состояния списка в историю отмены
+    fn push_undo(&mut self, snapshot: Snapshot) {
+        self.undo_stack.push(snapshot);
+        if self.undo_stack.len() > UNDO_LIMIT {
+            self.undo_stack.remove(0);
+        }
+        self.redo_stack.clear();
+        self.field_undo = None;
+    }
+
+    // Запоминание текущего списка перед изменением
+    fn record_undo(&mut self) {
+        self.push_undo((self.lists.clone(), self.current_list, self.trash.clone()));
+    }
+
+    // Запоминание состояния перед правкой поля задачи. Подряд идущие правки одного поля
+    // (ввод по буквам, перетаскивание в палитре) отменяются одним шагом.
+    fn record_field_undo(&mut self, index: usize, field: TaskField) {
+        if self.field_undo != Some((index, field)) {
+            self.record_undo();
+            self.field_undo = Some((index, field));
+        }
+    }
+
+    // Отмена последнего изменения
+    fn undo(&mut self) {
+        if let Some(previous) = self.undo_stack.pop() {
+            let current = self.replace_state(previous);
+            self.redo_stack.push(current);
+            self.reset_task_state();
+            self.autosave();
+        }
+    }
+
+    // Повтор отмененного изменения
+    fn redo(&mut self) {
+        if let Some(next) = self.redo_stack.pop() {
+            let current = self.replace_state(next);
+            self.undo_stack.push(current);
+            self.reset_task_state();
+            self.autosave();
+        }
+    }
+
+    // Замена списков и корзины сохраненным состоянием; возвращает прежнее состояние
+    fn replace_state(&mut self, (lists, current_list, trash): Snapshot) -> Snapshot {
+        (
+            std::mem::replace(&mut self.lists, lists),
+            std::mem::replace(&mut self.current_list, current_list),
+            std::mem::replace(&mut self.trash, trash),
+        )
+    }
+
+    // Сброс состояния, ссылающегося на индексы задач, после замены списка
+    fn reset_task_state(&mut self) {
+        self.selected_task = None;
+        self.solo_task = None;
+        self.completion_prompt = None;
+        self.edit_snapshot = None;
+        self.subtask_snapshot = None;
+        self.field_undo = None;
+        self.pending_delete = None;
+        self.marked.clear();
+        self.notes_open.clear();
+        self.active_timer = None;
+        self.focus_description = None;
+    }
+
+    // Добавление задачи из поля ввода с разбором быстрых меток; пустой ввод игнорируется
+    fn add_task_from_input(&mut self) -> bool {
+        let task = parse_quick_add(&self.new_task);
+        if task.description.is_empty() || self.exceeds_max_len(&task.description) {
+            return false;
+        }
+        self.log_action(&format!("add: {}", task.description));
+        self.record_undo();
+        self.tasks_mut().push(task);
+        self.new_task.clear();
+        self.autosave(); // Автосохранение
+        true
+    }
+
+    // Запись действия в журнал (если включено); старый журнал переименовывается в .1
+    fn log_action(&mut self, action: &str) {
+        if !self.settings.activity_log {
+            return;
+        }
+        let log_path = self.sibling_path(ACTIVITY_LOG);
+        let result = (|| -> std::io::Result<()> {
+            if fs::metadata(&log_path).is_ok_and(|meta| meta.len() > ACTIVITY_LOG_MAX_BYTES) {
+                fs::rename(&log_path, with_suffix(&log_path, ".1"))?;
+            }
+            let mut file = fs::OpenOptions::new().create(true).append(true).open(&log_path)?;
+            let line = action.replace('\n', " ");
+            writeln!(file, "{} {}", Local::now().format("%Y-%m-%d %H:%M:%S"), line)
+        })();
+        if let Err(error) = result {
+            self.last_error = Some(format!("Failed to write {}: {}", log_path.display(), error));
+        }
+    }
+
+    // Предпросмотр импорта из текстового, Markdown- или CSV-файла: файл читается и разбирается,
+    // но список не меняется до подтверждения
+    fn preview_import(&self, path: &Path, format: ImportFormat) -> Result<ImportPreview, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|error| format!("Failed to read {}: {}", path.display(), error))?;
+        let (tasks, errors) = match format {
+            ImportFormat::Text => (markdown::parse_lines(&text), Vec::new()),
+            ImportFormat::Csv => csv::from_csv(&text)
+                .map_err(|error| format!("Failed to import {}: {}", path.display(), error))?,
+        };
+        // Повтором считается задача, которая уже есть в списке или встретилась в файле раньше
+        let mut seen = HashSet::new();
+        let duplicates = tasks.iter()
+            .filter(|task| {
+                let description = normalize_description(&task.description);
+                !seen.insert(description) || self.is_duplicate(&task.description, None)
+            })
+            .count();
+        Ok(ImportPreview { path: path.to_path_buf(), tasks, duplicates, errors })
+    }
+
+    // Добавление задач из подтвержденного предпросмотра в конец списка. Ошибочные строки CSV
+    // не импортируются, а перечисляются в ошибке.
+    fn apply_import(&mut self, preview: ImportPreview) -> Result<usize, String> {
+        let count = preview.tasks.len();
+        if count > 0 {
+            self.record_undo();
+            self.tasks_mut().extend(preview.tasks);
+            self.log_action(&format!("import {} tasks from {}", count, preview.path.display()));
+            self.autosave();
+        }
+        if preview.errors.is_empty() {
+            Ok(count)
+        } else {
+            Err(format!(
+                "Imported {} tasks from {}; skipped {} malformed rows: {}",
+                count,
+                preview.path.display(),
+                preview.errors.len(),
+                preview.errors.join("; ")
+            ))
+        }
+    }
+
+    // Замена задач содержимым резервной копии; настройки не меняются, действие можно отменить
+    fn restore_backup(&mut self, path: &Path) -> Result<(), String> {
+        let mut backup = read_task_file(path).ok_or_else(|| format!("Failed to read {}", path.display()))?;
+        backup.migrate();
+        self.record_undo();
+        self.lists = backup.lists;
+        self.current_list = backup.current_list;
+        self.trash = backup.trash;
+        self.reset_task_state();
+        self.log_action(&format!("restore backup {}", path.display()));
+        self.autosave();
+        Ok(())
+    }
+
+    // Перенос задачи в другой файл задач (файл создается, если его нет)
+    fn move_task_to(&mut self, index: usize, dest_path: &Path) -> Result<(), String> {
+        let same_file = dest_path == self.save_path
+            || (dest_path.exists() && fs::canonicalize(dest_path).ok() == fs::canonicalize(&self.save_path).ok());
+        if same_file {
+            return Err("The task is already in this list".to_string());
+        }
+        let mut destination = match fs::read_to_string(dest_path) {
+            Ok(data) => serde_json::from_str::<TodoApp>(&data)
+                .map_err(|error| format!("{} is not a task file: {}", dest_path.display(), error))?,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => TodoApp::default(),
+            Err(error) => return Err(format!("Failed to read {}: {}", dest_path.display(), error)),
+        };
+        destination.migrate();
+        let task = self.tasks()[index].clone();
+        destination.tasks_mut().push(task);
+        let data = serde_json::to_string_pretty(&destination).map_err(|error| error.to_string())?;
+        write_atomically(dest_path, data.as_bytes())
+            .map_err(|error| format!("Failed to write {}: {}", dest_path.display(), error))?;
+
+        let entry = format!("move to {}: {}", dest_path.display(), self.tasks()[index].description);
+        self.log_action(&entry);
+        self.remove_task(index);
+        // Отмена вернула бы задачу в список, хотя она уже записана в другой файл
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.autosave();
+        self.flush_save(); // Задача не должна остаться в обоих файлах
+        Ok(())
+    }
+
+    // Удаление задачи после подтверждения
+    fn delete_task(&mut self, index: usize) {
+        self.record_undo();
+        let entry = format!("delete: {}", self.tasks()[index].description);
+        self.log_action(&entry);
+        self.trash_task(index);
+        self.autosave(); // Автосохранение
+    }
+
+    // Удаление всех выполненных задач
+    fn clear_completed(&mut self) {
+        if self.completed_tasks() == 0 {
+            return;
+        }
+        self.record_undo();
+        for index in (0..self.tasks().len()).rev() {
+            if self.tasks()[index].completed {
+                let entry = format!("delete: {}", self.tasks()[index].description);
+                self.log_action(&entry);
+                self.trash_task(index);
+            }
+        }
+        self.autosave(); // Автосохранение
+    }
+
+    // Перенос выполненных задач открытого списка в архив. В отличие от корзины архив
+    // хранится отдельно и не очищается. Отмена вернула бы в список задачи, которые уже
+    // лежат в архиве, поэтому история отмены сбрасывается.
+    fn archive_completed(&mut self) -> Result<usize, String> {
+        let archive_path = self.sibling_path(ARCHIVE_FILE);
+        let mut archive = read_archive(&archive_path)?;
+        let archived_at = Local::now().to_rfc3339();
+        let list = &self.lists[self.current_list];
+        let completed: Vec<ArchivedTask> = list.tasks.iter()
+            .filter(|task| task.completed)
+            .map(|task| ArchivedTask { task: task.clone(), list: list.name.clone(), archived_at: archived_at.clone() })
+            .collect();
+        if completed.is_empty() {
+            return Ok(0);
+        }
+        let count = completed.len();
+        archive.extend(completed);
+        let data = serde_json::to_string_pretty(&archive).map_err(|error| error.to_string())?;
+        fs::create_dir_all(self.task_dir())
+            .and_then(|_| write_atomically(&archive_path, data.as_bytes()))
+            .map_err(|error| format!("Failed to write {}: {}", archive_path.display(), error))?;
+
+        for index in (0..self.tasks().len()).rev() {
+            if self.tasks()[index].completed {
+                let entry = format!("archive: {}", self.tasks()[index].description);
+                self.log_action(&entry);
+                self.remove_task(index);
+            }
+        }
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.autosave();
+        self.flush_save(); // Задачи не должны остаться и в архиве, и в списке
+        Ok(count)
+    }
+
+    // Обновление статуса задачи по ее шагам с записью в журнал
+    fn sync_subtasks(&mut self, index: usize) {
+        if let Some(completed) = self.tasks_mut()[index].sync_with_subtasks() {
+            let action = if completed { "complete" } else { "reopen" };
+            let entry = format!("{}: {}", action, self.tasks()[index].description);
+            self.log_action(&entry);
+        }
+    }
+
+    // Отметка выполненными всех отмеченных задач
+    fn complete_marked(&mut self) {
+        let mut indices: Vec<usize> = self.marked.iter()
+            .copied()
+            .filter(|&index| !self.tasks()[index].completed)
+            .collect();
+        if indices.is_empty() {
+            return;
+        }
+        indices.sort_unstable();
+        self.record_undo();
+        let now = Local::now().to_rfc3339();
+        for index in indices {
+            let task = &mut self.tasks_mut()[index];
+            task.completed = true;
+            task.completed_at = Some(now.clone());
+            task.touch();
+            let entry = format!("complete: {}", task.description);
+            self.log_action(&entry);
+        }
+        self.marked.clear();
+        self.autosave(); // Одно сохранение на всю группу
+    }
+
+    // Смена приоритета у всех отмеченных задач одним шагом отмены
+    fn set_marked_priority(&mut self, priority: Priority) {
+        let mut indices: Vec<usize> = self.marked.iter()
+            .copied()
+            .filter(|&index| self.tasks()[index].priority != priority)
+            .collect();
+        if indices.is_empty() {
+            return;
+        }
+        indices.sort_unstable();
+        self.record_undo();
+        for index in indices {
+            let task = &mut self.tasks_mut()[index];
+            task.priority = priority;
+            task.touch();
+            let entry = format!("priority {}: {}", priority.label(), task.description);
+            self.log_action(&entry);
+        }
+        self.marked.clear();
+        self.autosave(); // Одно сохранение на всю группу
+    }
+
+    // Удаление отмеченных задач после подтверждения
+    fn delete_marked(&mut self) {
+        if self.marked.is_empty() {
+            return;
+        }
+        self.record_undo();
+        let mut indices: Vec<usize> = self.marked.drain().collect();
+        indices.sort_unstable();
+        // С конца списка, чтобы удаление не сдвигало еще не удаленные индексы
+        for index in indices.into_iter().rev() {
+            let entry = format!("delete: {}", self.tasks()[index].description);
+            self.log_action(&entry);
+            self.trash_task(index);
+        }
+        self.autosave(); // Одно сохранение на всю группу
+    }
+
+    // Копия задачи сразу после оригинала: невыполненная, с новым временем создания
+    fn duplicate_task(&mut self, index: usize) {
+        self.record_undo();
+        let original = &self.tasks()[index];
+        let copy = Task {
+            completed: false,
+            completed_at: None,
+            completion_note: None,
+            updated_at: None,
+            created_at: Local::now().to_rfc3339(),
+            subtasks: original.subtasks.iter()
+                .map(|subtask| Subtask { completed: false, ..subtask.clone() })
+                .collect(),
+            ..original.clone()
+        };
+        let entry = format!("duplicate: {}", copy.description);
+        self.log_action(&entry);
+        self.insert_task(index + 1, copy);
+        self.autosave();
+    }
+
+    // Вставка задачи с поправкой индексов, указывающих на последующие задачи
+    fn insert_task(&mut self, index: usize, task: Task) {
+        self.tasks_mut().insert(index, task);
+        let shift = |i: usize| if i >= index { i + 1 } else { i };
+        self.selected_task = self.selected_task.map(shift);
+        self.solo_task = self.solo_task.map(shift);
+        self.pending_delete = self.pending_delete.map(shift);
+        self.marked = self.marked.iter().map(|&marked| shift(marked)).collect();
+        self.notes_open = self.notes_open.iter().map(|&open| shift(open)).collect();
+        if let Some((task_index, _)) = &mut self.active_timer {
+            *task_index = shift(*task_index);
+        }
+        if let Some((task_index, _)) = &mut self.completion_prompt {
+            *task_index = shift(*task_index);
+        }
+    }
+
+    // Обмен двух задач местами вместе с состоянием, привязанным к их индексам
+    fn swap_tasks(&mut self, a: usize, b: usize) {
+        self.record_undo();
+        self.tasks_mut().swap(a, b);
+        let swap = |i: usize| if i == a { b } else if i == b { a } else { i };
+        self.selected_task = self.selected_task.map(swap);
+        self.solo_task = self.solo_task.map(swap);
+        self.pending_delete = self.pending_delete.map(swap);
+        self.marked = self.marked.iter().map(|&marked| swap(marked)).collect();
+        self.notes_open = self.notes_open.iter().map(|&open| swap(open)).collect();
+        if let Some((task_index, _)) = &mut self.active_timer {
+            *task_index = swap(*task_index);
+        }
+        if let Some((task_index, _)) = &mut self.completion_prompt {
+            *task_index = swap(*task_index);
+        }
+        for snapshot in [&mut self.edit_snapshot, &mut self.subtask_snapshot].into_iter().flatten() {
+            snapshot.swap(a, b);
+        }
+        let entry = format!("reorder: {}", self.tasks()[b].description);
+        self.log_action(&entry);
+        self.autosave();
+    }
+
+    // Завершение правки шагов задачи: состояние до правки попадает в историю отмены,
+    // если текст шагов изменился
+    fn finish_subtask_edit(&mut self, index: usize) {
+        let Some(snapshot) = self.subtask_snapshot.take() else {
+            return;
+        };
+        let steps = |task: &Task| task.subtasks.iter().map(|subtask| subtask.description.clone()).collect::<Vec<_>>();
+        let changed = snapshot.get(index).map(steps) != self.tasks().get(index).map(steps);
+        if changed {
+            let mut lists = self.lists.clone();
+            lists[self.current_list].tasks = snapshot;
+            self.push_undo((lists, self.current_list, self.trash.clone()));
+        }
+        self.autosave();
+    }
+
+    // Сохранение заметки о выполнении; пустая заметка не сохраняется
+    fn save_completion_note(&mut self, index: usize, note: &str) {
+        let note = note.trim();
+        if note.is_empty() {
+            return;
+        }
+        self.record_undo();
+        let task = &mut self.tasks_mut()[index];
+        task.completion_note = Some(note.to_string());
+        task.touch();
+        self.log_action(&format!("note: {}", note));
+        self.autosave();
+    }
+
+    // Проверка помидора: по истечении времени задаче засчитывается помидор
+    fn tick_pomodoro(&mut self, ctx: &egui::Context) {
+        let Some((index, started)) = self.active_timer else {
+            return;
+        };
+        if started.elapsed() < POMODORO {
+            return;
+        }
+        self.active_timer = None;
+        let task = &mut self.lists[self.current_list].tasks[index];
+        task.pomodoros += 1;
+        task.focus_secs += POMODORO.as_secs();
+        task.touch();
+        self.finished_pomodoro = Some((task.description.clone(), Instant::now()));
+        let entry = format!("pomodoro: {}", task.description);
+        self.log_action(&entry);
+        self.autosave();
+        ctx.send_viewport_cmd(egui::ViewportCommand::RequestUserAttention(egui::UserAttentionType::Informational));
+    }
+
+    // Остановка помидора: помидор засчитывается только целиком, а отработанное время
+    // прибавляется задаче; задачи сразу сохраняются
+    fn stop_timer_and_save(&mut self) {
+        if let Some((index, started)) = self.active_timer.take() {
+            let elapsed = started.elapsed().min(POMODORO).as_secs();
+            let task = &mut self.lists[self.current_list].tasks[index];
+            task.focus_secs += elapsed;
+            task.touch();
+            let entry = format!("pomodoro stopped after {} min: {}", elapsed / 60, task.description);
+            self.log_action(&entry);
+        }
+        self.flush_save();
+    }
+
+    // Перенос срока задачи на выбранную дату с подтверждением во всплывающем сообщении
+    fn snooze_task(&mut self, index: usize, date: NaiveDate) {
+        self.record_undo();
+        let date = date.format("%Y-%m-%d").to_string();
+        let task = &mut self.tasks_mut()[index];
+        task.due_date = Some(date.clone());
+        task.notified = false;
+        task.touch();
+        let entry = format!("snooze until {}: {}", date, task.description);
+        self.log_action(&entry);
+        self.autosave();
+        let message = i18n::format(self.settings.language, "Snoozed until {}", &[&date]);
+        self.toast = Some((message, Instant::now()));
+    }
+
+    // Сегодняшний день с учетом часа смены дня: после полуночи до этого часа еще идет вчерашний
+    fn today(&self) -> NaiveDate {
+        effective_date(Local::now(), self.settings.day_rollover_hour)
+    }
+
+    // Уведомление о задачах, ставших просроченными; каждая задача уведомляет один раз
+    fn notify_overdue(&mut self) {
+        let today = self.today();
+        let mut overdue = Vec::new();
+        for task in self.lists.iter_mut().flat_map(|list| list.tasks.iter_mut()) {
+            if task.is_overdue(today) && !task.notified {
+                task.notified = true;
+                overdue.push(task.description.clone());
+            }
+        }
+        if overdue.is_empty() {
+            return;
+        }
+        self.autosave();
+        if self.settings.mute_notifications {
+            return;
+        }
+        let lang = self.settings.language;
+        // Много задач сразу (например, при запуске) — одно общее уведомление
+        let body = match overdue.as_slice() {
+            [description] => description.clone(),
+            _ => i18n::format(lang, "{} tasks are overdue", &[&overdue.len()]),
+        };
+        notify::send(i18n::translate(lang, "Task overdue"), &body);
+    }
+
+    // Перемещение задачи в корзину
+    fn trash_task(&mut self, index: usize) {
+        let task = self.tasks()[index].clone();
+        self.remove_task(index);
+        self.trash.push(TrashedTask { task, deleted_at: Local::now().to_rfc3339() });
+    }
+
+    // Возврат задачи из корзины в конец списка
+    fn restore_from_trash(&mut self, index: usize) {
+        self.record_undo();
+        let trashed = self.trash.remove(index);
+        let entry = format!("restore: {}", trashed.task.description);
+        self.log_action(&entry);
+        self.tasks_mut().push(trashed.task);
+        self.autosave();
+    }
+
+    // Окончательное удаление задачи из корзины
+    fn purge_from_trash(&mut self, index: usize) {
+        self.record_undo();
+        let trashed = self.trash.remove(index);
+        let entry = format!("purge: {}", trashed.task.description);
+        self.log_action(&entry);
+        self.autosave();
+    }
+
+    // Очистка корзины от задач, удаленных больше TRASH_RETENTION_DAYS дней назад.
+    // Задачи с некорректным временем удаления остаются.
+    fn purge_old_trash(&mut self) {
+        let cutoff = Local::now() - chrono::Duration::days(TRASH_RETENTION_DAYS);
+        let before = self.trash.len();
+        self.trash.retain(|trashed| parse_timestamp(&trashed.deleted_at).is_none_or(|time| time >= cutoff));
+        if self.trash.len() != before {
+            self.autosave();
+        }
+    }
+
+    // Удаление задачи с поправкой индекса редактируемой задачи
+    fn remove_task(&mut self, index: usize) {
+        self.tasks_mut().remove(index);
+        self.selected_task = shift_index(self.selected_task, index);
+        self.solo_task = shift_index(self.solo_task, index);
+        self.pending_delete = shift_index(self.pending_delete, index);
+        self.marked = self.marked.iter().filter_map(|&marked| shift_index(Some(marked), index)).collect();
+        self.notes_open = self.notes_open.iter().filter_map(|&open| shift_index(Some(open), index)).collect();
+        self.active_timer = self.active_timer.and_then(|(task_index, started)| {
+            shift_index(Some(task_index), index).map(|task_index| (task_index, started))
+        });
+        self.completion_prompt = self.completion_prompt.take().and_then(|(task_index, note)| {
+            shift_index(Some(task_index), index).map(|task_index| (task_index, note))
+        });
+    }
+
+    // Подсчет количества выполненных задач
+    fn completed_tasks(&self) -> usize {
+        self.tasks().iter().filter(|task| task.completed).count()
+    }
+
+    // Процент выполнения задач; при учете шагов частично выполненные задачи дают частичный вклад
+    fn progress(&self) -> f32 {
+        if self.tasks().is_empty() {
+            0.0
+        } else if self.settings.weight_subtasks {
+            let done: f32 = self.tasks().iter().map(Task::completion).sum();
+            (done / self.tasks().len() as f32) * 100.0
+        } else {
+            (self.completed_tasks() as f32 / self.tasks().len() as f32) * 100.0
+        }
+    }
+
+    // Переключение темы
+    fn toggle_theme(&mut self) {
+        self.settings.theme = match self.settings.theme {
+            Theme::Dark => Theme::Light,
+            Theme::Light => Theme::HighContrast,
+            Theme::HighContrast => Theme::Auto,
+            Theme::Auto => Theme::Dark,
+        };
+        self.autosave();
+    }
+
+    // Восстановление состояния из файла аварийного сохранения
+    fn recover_from_crash(&mut self) {
+        let recovery_path = with_suffix(&self.save_path, RECOVERY_SUFFIX);
+        match fs::read_to_string(&recovery_path).map(|data| serde_json::from_str::<TodoApp>(&data)) {
+            Ok(Ok(recovered)) => {
+                let save_path = std::mem::take(&mut self.save_path);
+                *self = recovered;
+                self.migrate();
+                self.save_path = save_path;
+                self.flush_save(); // Файл восстановления удаляется только после записи
+                let _ = fs::remove_file(&recovery_path);
+            }
+            Ok(Err(error)) => self.last_error = Some(format!("Failed to parse {}: {}", recovery_path.display(), error)),
+            Err(error) => self.last_error = Some(format!("Failed to read {}: {}", recovery_path.display(), error)),
+        }
+        self.recovery_available = false;
+    }
+
+    // Обновление снимка состояния для аварийного сохранения; снимок пересобирается
+    // только после изменений, а не каждый кадр
+    fn update_recovery_snapshot(&mut self) {
+        if !self.snapshot_stale {
+            return;
+        }
+        if let (Ok(data), Ok(mut snapshot)) = (serde_json::to_string(self), RECOVERY_SNAPSHOT.lock()) {
+            *snapshot = Some(data);
+            self.snapshot_stale = false;
+        }
+    }
+
+    // Длиннее ли текст допустимой длины описания
+    fn exceeds_max_len(&self, text: &str) -> bool {
+        self.settings.max_task_len > 0 && text.chars().count() > self.settings.max_task_len
+    }
+
+    // Ограничение длины для полей ввода описания
+    fn char_limit(&self) -> usize {
+        match self.settings.max_task_len {
+            0 => usize::MAX,
+            limit => limit,
+        }
+    }
+
+    // Цвет акцента из настроек
+    fn accent_color(&self) -> Option<egui::Color32> {
+        self.settings.accent.map(|[r, g, b]| egui::Color32::from_rgb(r, g, b))
+    }
+
+    // Сброс настроек к значениям по умолчанию
+    fn restore_default_settings(&mut self) {
+        self.settings = Settings::default();
+        self.autosave();
+    }
+
+    // Получение текущего времени в выбранном формате (по умолчанию ЧЧ:ММ:СС)
+    fn current_time(&self) -> String {
+        let now = Local::now();
+        now.format(self.settings.time_format.valid()).to_string()
+    }
+
+    // Фильтрация задач по поисковому запросу
+    fn filtered_tasks(&self) -> Vec<(usize, &Task)> {
+        self.tasks().iter()
+            .enumerate()
+            .filter(|(_, task)| self.search_score(task).is_some())
+            .filter(|(_, task)| self.tag_filter.as_ref().is_none_or(|tag| task.tags.contains(tag)))
+            .filter(|(_, task)| self.color_filter.is_none_or(|color| task.color == Some(color)))
+            .filter(|(_, task)| !self.stale_filter || task.is_stale(Local::now(), self.settings.stale_days.get()))
+            .collect()
+    }
+
+    // Залежавшиеся задачи открытого списка: невыполненные и созданные больше `days` дней назад
+    fn stale_tasks(&self, days: u32) -> Vec<usize> {
+        let now = Local::now();
+        self.tasks().iter()
+            .enumerate()
+            .filter(|(_, task)| task.is_stale(now, days))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    // Число задач для каждого фильтра по статусу (в порядке StatusFilter::ALL)
+    // среди задач, подходящих под поиск, тег и цвет
+    fn status_counts(&self) -> [usize; 4] {
+        let today = self.today();
+        let filtered = self.filtered_tasks();
+        StatusFilter::ALL.map(|filter| filtered.iter().filter(|(_, task)| filter.matches(task, today)).count())
+    }
+
+    // Совпадение задачи с поисковым запросом: None — не подходит. Сначала проверяются
+    // условия `priority:`, `tag:` и `due:`, затем оставшийся текст.
+    // В обычном режиме ищется подстрока без учета регистра, все совпадения равноценны.
+    fn search_score(&self, task: &Task) -> Option<i64> {
+        let search = SearchQuery::parse(&self.search_query);
+        if !search.matches(task, self.today()) {
+            return None;
+        }
+        let query = search.text.trim();
+        if query.is_empty() {
+            Some(0)
+        } else if self.settings.fuzzy_search {
+            // Совпадение в описании важнее совпадения в заметках
+            fuzzy::score(&task.description, query).or_else(|| fuzzy::score(&task.notes, query).map(|score| score / 2))
+        } else {
+            let query = search.text.to_lowercase();
+            (task.description.to_lowercase().contains(&query) || task.notes.to_lowercase().contains(&query))
+                .then_some(0)
+        }
+    }
+
+    // Все теги, встречающиеся в задачах, по алфавиту
+    fn all_tags(&self) -> Vec<String> {
+        let mut tags: Vec<String> = self.tasks().iter().flat_map(|task| task.tags.iter().cloned()).collect();
+        tags.sort();
+        tags.dedup();
+        tags
+    }
+
+    // Все цвета меток задач в порядке первого появления
+    fn all_colors(&self) -> Vec<[u8; 3]> {
+        let mut colors = Vec::new();
+        for color in self.tasks().iter().filter_map(|task| task.color) {
+            if !colors.contains(&color) {
+                colors.push(color);
+            }
+        }
+        colors
+    }
+
+    // Добавление тегов к задаче: ввод разбивается по пробелам, повторы пропускаются
+    fn add_tags(&mut self, index: usize, input: &str) -> bool {
+        let task = &self.tasks()[index];
+        let new_tags: Vec<String> = input.split_whitespace()
+            .map(str::to_string)
+            .filter(|tag| !task.tags.contains(tag))
+            .collect();
+        if new_tags.is_empty() {
+            return false;
+        }
+        self.record_undo();
+        let task = &mut self.tasks_mut()[index];
+        for tag in new_tags {
+            if !task.tags.contains(&tag) {
+                task.tags.push(tag);
+            }
+        }
+        task.touch();
+        self.autosave();
+        true
+    }
+
+    // Удаление тега у задачи
+    fn remove_tag(&mut self, index: usize, tag: &str) {
+        self.record_undo();
+        let task = &mut self.tasks_mut()[index];
+        task.tags.retain(|existing| existing != tag);
+        task.touch();
+        self.autosave();
+    }
+
+    // Отфильтрованные задачи в выбранном порядке сортировки.
+    // Индексы по-прежнему указывают на позиции в `self.tasks()`.
+    fn sorted_filtered_tasks(&self) -> Vec<(usize, &Task)> {
+        let mut tasks = self.filtered_tasks();
+        match self.settings.sort_mode {
+            SortMode::Manual => {}
+            // Без флага убывания важные задачи идут первыми
+            SortMode::Priority => tasks.sort_by_key(|(_, task)| std::cmp::Reverse(task.priority)),
+            SortMode::Created => tasks.sort_by_key(|(_, task)| parse_timestamp(&task.created_at)),
+            SortMode::Alphabetical => tasks.sort_by_key(|(_, task)| task.description.to_lowercase()),
+        }
+        if self.settings.sort_descending {
+            tasks.reverse();
+        }
+        // При нечетком поиске лучшие совпадения идут первыми; оценка считается один раз на задачу
+        if self.settings.fuzzy_search && !self.search_query.trim().is_empty() {
+            tasks.sort_by_cached_key(|(_, task)| std::cmp::Reverse(self.search_score(task)));
+        }
+        // Закрепленные задачи наверху; устойчивая сортировка сохраняет порядок внутри групп
+        tasks.sort_by_key(|(_, task)| !task.pinned);
+        tasks
+    }
+
+    // Индексы задач, видимых с учетом поиска, сортировки и фильтра по статусу
+    fn visible_task_indices(&self) -> Vec<usize> {
+        let today = self.today();
+        let completed_within = self.completed_within_filter();
+        self.sorted_filtered_tasks()
+            .iter()
+            .filter(|(_, task)| self.settings.status_filter.matches(task, today))
+            .filter(|(_, task)| completed_within.matches(task, today, self.settings.day_rollover_hour))
+            .map(|(i, _)| *i)
+            .collect()
+    }
+
+    // Упорядочивание выполненных задач от последней выполненной; задачи без времени в конце
+    fn sort_by_completion(&self, indices: &mut [usize]) {
+        indices.sort_by_cached_key(|&i| std::cmp::Reverse(self.tasks()[i].completion_time()));
+    }
+
+    // Есть ли в открытом списке задачи со временем выполнения
+    fn has_completion_times(&self) -> bool {
+        self.tasks().iter().any(|task| task.completed && task.completed_at.is_some())
+    }
+
+    // Действующий фильтр по времени выполнения: только вместе с фильтром "Выполненные"
+    // и только когда он показан (есть задачи со временем выполнения)
+    fn completed_within_filter(&self) -> CompletedWithin {
+        if self.settings.status_filter == StatusFilter::Completed && self.has_completion_times() {
+            self.settings.completed_within
+        } else {
+            CompletedWithin::AnyTime
+        }
+    }
+
+    // Экспорт видимых задач в PDF с отметками [ ]/[x]
+    fn export_pdf(&self, path: &Path) -> std::io::Result<()> {
+        let mut lines = Vec::new();
+        for i in self.visible_task_indices() {
+            let task = &self.tasks()[i];
+            let mark = if task.completed { "[x]" } else { "[ ]" };
+            for (n, line) in task.description.lines().enumerate() {
+                if n == 0 {
+                    lines.push(format!("{} {}", mark, line));
+                } else {
+                    lines.push(format!("    {}", line));
+                }
+            }
+        }
+        let subtitle = format!("Exported {}", Local::now().format("%Y-%m-%d %H:%M"));
+        fs::write(path, pdf::render("To-Do List", &subtitle, &lines))
+    }
+
+    // Журнал выполненных задач открытого списка по дням в Markdown
+    fn export_journal(&self) -> String {
+        markdown::to_journal(self.tasks(), self.settings.day_rollover_hour)
+    }
+
+    // Сводка по открытому списку в Markdown
+    fn export_stats(&self) -> String {
+        markdown::to_stats(self.tasks(), Local::now())
+    }
+
+    // Есть ли другая задача с таким же описанием (без учета регистра и лишних пробелов)
+    fn is_duplicate(&self, description: &str, except: Option<usize>) -> bool {
+        let description = normalize_description(description);
+        !description.is_empty()
+            && self.tasks().iter().enumerate().any(|(i, other)| {
+                Some(i) != except && normalize_description(&other.description) == description
+            })
+    }
+
+    // Выполненные задачи открытого списка: нормализованное описание и день последнего выполнения
+    // (None у старых задач без времени выполнения). Строится один раз за кадр для всех строк.
+    fn completion_history(&self) -> HashMap<String, Option<NaiveDate>> {
+        let mut history: HashMap<String, Option<NaiveDate>> = HashMap::new();
+        for task in self.tasks().iter().filter(|task| task.completed) {
+            let description = normalize_description(&task.description);
+            if description.is_empty() {
+                continue;
+            }
+            let day = task.completed_day(self.settings.day_rollover_hour);
+            let latest = history.entry(description).or_default();
+            *latest = (*latest).max(day);
+        }
+        history
+    }
+
+    // Выполнялась ли уже активная задача с таким же описанием: Some с днем последнего выполнения
+    fn done_before(&self, index: usize, history: &HashMap<String, Option<NaiveDate>>) -> Option<Option<NaiveDate>> {
+        let task = &self.tasks()[index];
+        if task.completed {
+            return None;
+        }
+        history.get(&normalize_description(&task.description)).copied()
+    }
+}
+
+// Модальное окно подтверждения: Some(true) — подтверждено, Some(false) — отменено,
+// None — решение еще не принято
+fn confirm_dialog(ctx: &egui::Context, lang: Lang, title: &str, message: &str) -> Option<bool> {
+    let mut answer = None;
+    egui::Window::new(title)
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(ctx, |ui| {
+            ui.label(message);
+            ui.horizontal(|ui| {
+                if ui.button(i18n::translate(lang, "Confirm")).clicked() {
+                    answer = Some(true);
+                }
+                if ui.button(i18n::translate(lang, "Cancel")).clicked() {
+                    answer = Some(false);
+                }
+            });
+        });
+    answer
+}
+
+// Высококонтрастная тема: белый текст на черном фоне и толстые контуры фокуса
+fn high_contrast_visuals() -> egui::Visuals {
+    let mut visuals = egui::Visuals::dark();
+    let outline = egui::Stroke::new(1.5, egui::Color32::WHITE);
+    let focus = egui::Stroke::new(3.0, egui::Color32::YELLOW);
+
+    visuals.override_text_color = Some(egui::Color32::WHITE);
+    visuals.panel_fill = egui::Color32::BLACK;
+    visuals.window_fill = egui::Color32::BLACK;
+    visuals.extreme_bg_color = egui::Color32::BLACK;
+    visuals.faint_bg_color = egui::Color32::from_gray(24);
+    visuals.selection.bg_fill = egui::Color32::from_rgb(0, 70, 160);
+    visuals.selection.stroke = focus;
+
+    let widgets = &mut visuals.widgets;
+    for state in [&mut widgets.noninteractive, &mut widgets.inactive, &mut widgets.open] {
+        state.bg_stroke = outline;
+        state.fg_stroke = outline;
+    }
+    widgets.inactive.bg_fill = egui::Color32::BLACK;
+    widgets.inactive.weak_bg_fill = egui::Color32::BLACK;
+    widgets.hovered.bg_stroke = focus;
+    widgets.hovered.fg_stroke = outline;
+    widgets.active.bg_stroke = focus;
+    widgets.active.fg_stroke = focus;
+    visuals
+}
+
+// Круговой индикатор прогресса: дуга от верхней точки по часовой стрелке
+fn progress_ring(ui: &mut egui::Ui, progress: f32) {
+    let size = egui::vec2(32.0, 32.0);
+    let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+    let painter = ui.painter();
+    let center = rect.center();
+    let radius = rect.width() / 2.0 - 3.0;
+
+    let track = egui::Stroke::new(4.0, ui.visuals().widgets.inactive.bg_fill);
+    painter.circle_stroke(center, radius, track);
+
+    let progress = progress.clamp(0.0, 1.0);
+    if progress > 0.0 {
+        let segments = (64.0 * progress).ceil() as usize;
+        let start = -std::f32::consts::FRAC_PI_2;
+        let sweep = std::f32::consts::TAU * progress;
+        let points: Vec<egui::Pos2> = (0..=segments)
+            .map(|n| {
+                let angle = start + sweep * n as f32 / segments as f32;
+                center + radius * egui::vec2(angle.cos(), angle.sin())
+            })
+            .collect();
+        let fill = egui::Stroke::new(4.0, ui.visuals().selection.bg_fill);
+        painter.add(egui::Shape::line(points, fill));
+    }
+}
+
+// Столбчатая диаграмма выполненных задач по дням; пустой день — только контур столбика
+fn completion_chart(ui: &mut egui::Ui, days: &[(NaiveDate, usize)], hint: impl Fn(NaiveDate, usize) -> String) {
+    const BAR_WIDTH: f32 = 28.0;
+    const BAR_GAP: f32 = 8.0;
+    const BAR_HEIGHT: f32 = 60.0;
+    let label_height = ui.text_style_height(&egui::TextStyle::Small);
+    let size = egui::vec2(days.len() as f32 * (BAR_WIDTH + BAR_GAP), BAR_HEIGHT + 2.0 * label_height + 4.0);
+    let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+    let max = days.iter().map(|&(_, count)| count).max().unwrap_or(0).max(1);
+    let small = egui::TextStyle::Small.resolve(ui.style());
+    let text_color = ui.visuals().weak_text_color();
+    let fill = ui.visuals().selection.bg_fill;
+    let outline = egui::Stroke::new(1.0, ui.visuals().widgets.inactive.bg_stroke.color);
+
+    for (n, &(date, count)) in days.iter().enumerate() {
+        let left = rect.left() + n as f32 * (BAR_WIDTH + BAR_GAP) + BAR_GAP / 2.0;
+        let bottom = rect.top() + label_height + BAR_HEIGHT;
+        let column = egui::Rect::from_min_max(egui::pos2(left, rect.top() + label_height), egui::pos2(left + BAR_WIDTH, bottom));
+        let height = BAR_HEIGHT * count as f32 / max as f32;
+        let bar = egui::Rect::from_min_max(egui::pos2(left, bottom - height), column.right_bottom());
+        let painter = ui.painter();
+        if count == 0 {
+            painter.rect_stroke(column, 2.0, outline);
+        } else {
+            painter.rect_filled(bar, 2.0, fill);
+            painter.text(bar.center_top(), egui::Align2::CENTER_BOTTOM, count.to_string(), small.clone(), text_color);
+        }
+        let day = date.format("%d.%m").to_string();
+        painter.text(egui::pos2(column.center().x, bottom + 2.0), egui::Align2::CENTER_TOP, day, small.clone(), text_color);
+        ui.interact(column, ui.id().with(("completion_day", n)), egui::Sense::hover())
+            .on_hover_text(hint(date, count));
+    }
+}
+
+// Чтение и разбор файла задач
+fn read_task_file(path: &Path) -> Option<TodoApp> {
+    let data = fs::read_to_string(path).ok()?;
+    serde_json::from_str::<TodoApp>(&data).ok()
+}
+
+// Чтение архива; отсутствующий файл — пустой архив, а поврежденный не перезаписывается
+fn read_archive(path: &Path) -> Result<Vec<ArchivedTask>, String> {
+    match fs::read_to_string(path) {
+        Ok(data) => serde_json::from_str(&data).map_err(|error| format!("Failed to parse {}: {}", path.display(), error)),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(error) => Err(format!("Failed to read {}: {}", path.display(), error)),
+    }
+}
+
+// Копия файла задач с отметкой времени; старые копии сверх BACKUP_LIMIT удаляются
+fn create_backup(save_path: &Path) -> std::io::Result<()> {
+    let stamp = Local::now().format("%Y%m%d-%H%M%S");
+    fs::copy(save_path, with_suffix(save_path, &format!("{}{}", BACKUP_MARKER, stamp)))?;
+    for (old, _) in backup_files(save_path).into_iter().skip(BACKUP_LIMIT) {
+        fs::remove_file(old)?;
+    }
+    Ok(())
+}
+
+// Резервные копии файла задач от новых к старым вместе с временем создания для показа
+fn backup_files(save_path: &Path) -> Vec<(PathBuf, String)> {
+    let (Some(dir), Some(name)) = (save_path.parent(), save_path.file_name()) else {
+        return Vec::new();
+    };
+    let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+    let prefix = format!("{}{}", name.to_string_lossy(), BACKUP_MARKER);
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut backups: Vec<(PathBuf, String)> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            let stamp = file_name.strip_prefix(&prefix)?;
+            let time = chrono::NaiveDateTime::parse_from_str(stamp, "%Y%m%d-%H%M%S").ok()?;
+            Some((entry.path(), time.format("%Y-%m-%d %H:%M:%S").to_string()))
+        })
+        .collect();
+    // Отметка времени в имени упорядочивается так же, как время
+    backups.sort_by(|a, b| b.0.cmp(&a.0));
+    backups
+}
+
+// Путь с добавленным суффиксом, например tasks.json.tmp
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    name.into()
+}
+
+// Путь к файлу задач: аргумент --file, затем переменная окружения, затем каталог настроек.
+// Уже существующий tasks.json в текущем каталоге продолжает использоваться.
+fn resolve_save_path(args: &[String]) -> PathBuf {
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--file" {
+            if let Some(path) = args.next() {
+                return PathBuf::from(path);
+            }
+        } else if let Some(path) = arg.strip_prefix("--file=") {
+            return PathBuf::from(path);
+        }
+    }
+    if let Some(path) = std::env::var_os(FILE_ENV_VAR).filter(|path| !path.is_empty()) {
+        return PathBuf::from(path);
+    }
+    match config_dir() {
+        Some(dir) if !Path::new(SAVE_FILE).exists() => dir.join(APP_DIR).join(SAVE_FILE),
+        _ => PathBuf::from(SAVE_FILE),
+    }
+}
+
+// Системный каталог настроек пользователя
+fn config_dir() -> Option<PathBuf> {
+    let env_dir = |name: &str| std::env::var_os(name).filter(|dir| !dir.is_empty()).map(PathBuf::from);
+    if cfg!(windows) {
+        env_dir("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        env_dir("HOME").map(|home| home.join("Library").join("Application Support"))
+    } else {
+        env_dir("XDG_CONFIG_HOME").or_else(|| env_dir("HOME").map(|home| home.join(".config")))
+    }
+}
+
+// Атомарная запись: данные пишутся во временный файл и переименовываются поверх основного,
+// поэтому прерванная запись не портит существующий файл
+fn write_atomically(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let temp = with_suffix(path, ".tmp");
+    let mut file = fs::File::create(&temp)?;
+    file.write_all(data)?;
+    file.sync_all()?;
+    fs::rename(&temp, path)
+}
+
+// Другие файлы задач (*.json) в каталоге `dir`, кроме текущего `exclude` и архива
+fn other_task_files(dir: &Path, exclude: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let exclude = exclude.file_name().unwrap_or_default().to_string_lossy();
+    let mut files: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .filter(|name| name.ends_with(".json") && *name != exclude && name != ARCHIVE_FILE)
+        .collect();
+    files.sort();
+    files
+}
+
+// Разбор сохраненной метки времени в местное время
+fn parse_timestamp(timestamp: &str) -> Option<chrono::DateTime<Local>> {
+    chrono::DateTime::parse_from_rfc3339(timestamp)
+        .ok()
+        .map(|time| time.with_timezone(&Local))
+}
+
+// День, к которому относится момент времени: до часа смены дня `rollover_hour` еще идет предыдущий
+fn effective_date(time: chrono::DateTime<Local>, rollover_hour: u8) -> NaiveDate {
+    (time.naive_local() - chrono::Duration::hours(rollover_hour as i64)).date()
+}
+
+// Разбор срока выполнения в формате ГГГГ-ММ-ДД
+fn parse_due_date(date: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d").ok()
+}
+
+// Задача из строки быстрого ввода: `!high`/`!medium`/`!low` задают приоритет, `@ГГГГ-ММ-ДД` —
+// срок, `#тег` добавляет тег. Распознанные слова убираются из описания, остальной текст не меняется.
+fn parse_quick_add(input: &str) -> Task {
+    let mut task = Task::new(String::new());
+    let mut lines = Vec::new();
+    for line in input.lines() {
+        let mut words = Vec::new();
+        for word in line.split_whitespace() {
+            let priority = word.strip_prefix('!')
+                .and_then(|name| Priority::ALL.into_iter().find(|priority| priority.label().eq_ignore_ascii_case(name)));
+            let due = word.strip_prefix('@').filter(|date| parse_due_date(date).is_some());
+            let tag = word.strip_prefix('#').filter(|name| !name.is_empty() && !name.starts_with('#'));
+            if let Some(priority) = priority {
+                task.priority = priority;
+            } else if let Some(due) = due {
+                task.due_date = Some(due.to_string());
+            } else if tag.is_some() {
+                if !task.tags.iter().any(|existing| existing == word) {
+                    task.tags.push(word.to_string());
+                }
+            } else {
+                words.push(word);
+            }
+        }
+        // Строка без меток сохраняется вместе с пробелами
+        let has_tokens = words.len() < line.split_whitespace().count();
+        lines.push(if has_tokens { words.join(" ") } else { line.to_string() });
+    }
+    task.description = lines.join("\n").trim().to_string();
+    task
+}
+
+// Условие на срок из поискового запроса (`due:...`)
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum DueToken {
+    Overdue,
+    Today,
+    Tomorrow,
+    None,
+    On(NaiveDate),
+}
+
+impl DueToken {
+    // Разбор значения после `due:`: overdue, today, tomorrow, none или ГГГГ-ММ-ДД
+    fn parse(value: &str) -> Option<DueToken> {
+        match value.to_lowercase().as_str() {
+            "overdue" => Some(DueToken::Overdue),
+            "today" => Some(DueToken::Today),
+            "tomorrow" => Some(DueToken::Tomorrow),
+            "none" => Some(DueToken::None),
+            _ => parse_due_date(value).map(DueToken::On),
+        }
+    }
+
+    fn matches(self, task: &Task, today: NaiveDate) -> bool {
+        match self {
+            DueToken::Overdue => task.is_overdue(today),
+            DueToken::Today => task.due() == Some(today),
+            DueToken::Tomorrow => task.due() == Some(today + chrono::Duration::days(1)),
+            DueToken::None => task.due().is_none(),
+            DueToken::On(date) => task.due() == Some(date),
+        }
+    }
+}
+
+// Поисковый запрос: слова `priority:`, `tag:` и `due:` задают условия на поля задачи,
+// остальной текст ищется в описании и заметках
+#[derive(Default)]
+struct SearchQuery {
+    text: String,
+    priority: Option<Priority>,
+    tags: Vec<String>, // Без `#`, в нижнем регистре; задача должна иметь все
+    due: Option<DueToken>,
+}
+
+impl SearchQuery {
+    // Разбор запроса; нераспознанное значение (например, `priority:urgent`) остается текстом
+    fn parse(query: &str) -> SearchQuery {
+        let mut search = SearchQuery::default();
+        let mut words = Vec::new();
+        for word in query.split_whitespace() {
+            let (key, value) = word.split_once(':').unwrap_or_default();
+            let priority = Priority::ALL.into_iter().find(|priority| priority.label().eq_ignore_ascii_case(value));
+            let tag = value.trim_start_matches('#');
+            let due = DueToken::parse(value);
+            match key.to_lowercase().as_str() {
+                "priority" if priority.is_some() => search.priority = priority,
+                "tag" if !tag.is_empty() => search.tags.push(tag.to_lowercase()),
+                "due" if due.is_some() => search.due = due,
+                _ => words.push(word),
+            }
+        }
+        // Запрос без условий ищется как есть, вместе с пробелами
+        let has_tokens = words.len() < query.split_whitespace().count();
+        search.text = if has_tokens { words.join(" ") } else { query.to_string() };
+        search
+    }
+
+    // Подходит ли задача под условия на поля (текст проверяется отдельно)
+    fn matches(&self, task: &Task, today: NaiveDate) -> bool {
+        self.priority.is_none_or(|priority| task.priority == priority)
+            && self.tags.iter().all(|tag| task.tags.iter().any(|own| own.trim_start_matches('#').to_lowercase() == *tag))
+            && self.due.is_none_or(|due| due.matches(task, today))
+    }
+}
+
+// Поправка сохраненного индекса после удаления задачи с индексом `removed`
+fn shift_index(index: Option<usize>, removed: usize) -> Option<usize> {
+    match index {
+        Some(i) if i == removed => None,
+        Some(i) if i > removed => Some(i - 1),
+        other => other,
+    }
+}
+
+// Байтовые диапазоны всех вхождений `query` в `text` без учета регистра
+fn match_ranges(text: &str, query: &str) -> Vec<std::ops::Range<usize>> {
+    let query: Vec<char> = query.chars().flat_map(char::to_lowercase).collect();
+    let mut ranges = Vec::new();
+    if query.is_empty() {
+        return ranges;
+    }
+    let mut start = 0;
+    while start < text.len() {
+        // Сравнение посимвольно: строчная буква может занимать другое число байт
+        let mut matched = 0;
+        let mut end = start;
+        for (offset, c) in text[start..].char_indices() {
+            let lower: Vec<char> = c.to_lowercase().collect();
+            if !query[matched..].starts_with(&lower) {
+                break;
+            }
+            matched += lower.len();
+            end = start + offset + c.len_utf8();
+            if matched == query.len() {
+                break;
+            }
+        }
+        if matched == query.len() {
+            ranges.push(start..end);
+            start = end;
+        } else {
+            start += text[start..].chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    ranges
+}
+
+// Байтовые диапазоны символов, найденных нечетким поиском; соседние символы объединяются
+fn fuzzy_ranges(text: &str, query: &str) -> Vec<std::ops::Range<usize>> {
+    let indices = fuzzy::match_indices(text, query).unwrap_or_default();
+    let mut ranges: Vec<std::ops::Range<usize>> = Vec::new();
+    for (n, (start, c)) in text.char_indices().enumerate() {
+        if indices.binary_search(&n).is_err() {
+            continue;
+        }
+        let end = start + c.len_utf8();
+        match ranges.last_mut() {
+            Some(last) if last.end == start => last.end = end,
+            _ => ranges.push(start..end),
+        }
+    }
+    ranges
+}
+
+// Подсвечиваемые фрагменты текста: буквы нечеткого совпадения или вхождения запроса
+fn search_ranges(text: &str, query: &str, fuzzy: bool) -> Vec<std::ops::Range<usize>> {
+    if fuzzy {
+        fuzzy_ranges(text, query.trim())
+    } else {
+        match_ranges(text, query)
+    }
+}
+
+// Разметка текста с подсветкой фрагментов `ranges` (байтовые диапазоны по возрастанию)
+fn highlight_matches(text: &str, ranges: &[std::ops::Range<usize>], font_id: egui::FontId, color: egui::Color32) -> egui::text::LayoutJob {
+    let plain = egui::TextFormat::simple(font_id, color);
+    let highlighted = egui::TextFormat {
+        background: egui::Color32::from_rgba_unmultiplied(250, 200, 40, 110),
+        ..plain.clone()
+    };
+    let mut job = egui::text::LayoutJob::default();
+    let mut position = 0;
+    for range in ranges {
+        job.append(&text[position..range.start], 0.0, plain.clone());
+        job.append(&text[range.clone()], 0.0, highlighted.clone());
+        position = range.end;
+    }
+    job.append(&text[position..], 0.0, plain);
+    job
+}
+
+// Байтовые диапазоны ссылок http:// и https://. Ссылка тянется до пробела, но знаки
+// препинания в конце (и непарная закрывающая скобка) к ней не относятся.
+fn url_ranges(text: &str) -> Vec<std::ops::Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut position = 0;
+    while let Some(offset) = text[position..].find("http") {
+        let start = position + offset;
+        let rest = &text[start..];
+        let scheme = ["https://", "http://"].into_iter().find(|scheme| rest.starts_with(scheme));
+        // Схема внутри слова (например, "xhttp://") ссылкой не считается
+        let inside_word = text[..start].chars().next_back().is_some_and(char::is_alphanumeric);
+        let Some(scheme) = scheme.filter(|_| !inside_word) else {
+            position = start + "http".len();
+            continue;
+        };
+        let mut url = &rest[..rest.find(char::is_whitespace).unwrap_or(rest.len())];
+        loop {
+            url = url.trim_end_matches(['.', ',', ';', ':', '!', '?', '"', '\'']);
+            match url.strip_suffix(')') {
+                Some(inner) if inner.matches('(').count() <= inner.matches(')').count() => url = inner,
+                _ => break,
+            }
+        }
+        if url.len() > scheme.len() {
+            ranges.push(start..start + url.len());
+        }
+        position = start + url.len().max(scheme.len());
+    }
+    ranges
+}
+
+// Квадратный образец цвета, работающий как кнопка; выбранный обводится
+fn color_swatch(ui: &mut egui::Ui, [r, g, b]: [u8; 3], selected: bool) -> egui::Response {
+    let (rect, response) = ui.allocate_exact_size(egui::vec2(16.0, 16.0), egui::Sense::click());
+    let stroke = if selected {
+        egui::Stroke::new(2.0, ui.visuals().strong_text_color())
+    } else {
+        ui.visuals().widgets.inactive.bg_stroke
+    };
+    ui.painter().rect(rect, 3.0, egui::Color32::from_rgb(r, g, b), stroke);
+    response
+}
+
+// Цвет акцента для кнопок, выделения и ссылок
+fn apply_accent(visuals: &mut egui::Visuals, accent: egui::Color32) {
+    visuals.selection.bg_fill = accent;
+    visuals.hyperlink_color = accent;
+    visuals.widgets.inactive.weak_bg_fill = accent.gamma_multiply(0.6);
+    visuals.widgets.hovered.weak_bg_fill = accent.gamma_multiply(0.8);
+    visuals.widgets.hovered.bg_stroke.color = accent;
+    visuals.widgets.active.weak_bg_fill = accent;
+}
+
+// Нормализация описания для сравнения: без регистра и лишних пробелов
+fn normalize_description(description: &str) -> String {
+    description
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+// Обработчик сигнала: только выставляет флаг, сохранение выполняется в `update`.
+// Повторный сигнал завершает процесс сразу, если окно не успело закрыться.
+#[cfg(unix)]
+extern "C" fn handle_shutdown_signal(_signal: libc::c_int) {
+    if SHUTDOWN_REQUESTED.swap(true, Ordering::SeqCst) {
+        unsafe { libc::_exit(130) };
+    }
+}
+
+// Установка обработчиков SIGINT/SIGTERM для сохранения задач перед выходом
+#[cfg(unix)]
+fn install_signal_handlers() {
+    let handler = handle_shutdown_signal as extern "C" fn(libc::c_int) as *const () as libc::sighandler_t;
+    unsafe {
+        libc::signal(libc::SIGINT, handler);
+        libc::signal(libc::SIGTERM, handler);
+    }
+}
+
+#[cfg(not(unix))]
+fn install_signal_handlers() {}
+
+// Аварийное сохранение при панике. Работает по принципу "как получится":
+// записывается последний снимок состояния, если его удается получить без блокировки.
+fn install_panic_hook(recovery_path: PathBuf) {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if let Ok(snapshot) = RECOVERY_SNAPSHOT.try_lock() {
+            if let Some(data) = snapshot.as_ref() {
+                let _ = fs::write(&recovery_path, data);
+            }
+        }
+        default_hook(info);
+    }));
+}
+
+impl eframe::App for TodoApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // Перевод надписей; язык копируется, чтобы не занимать `self` в замыканиях
+        let lang = self.settings.language;
+        let t = |key: &'static str| i18n::translate(lang, key);
+        let tf = |key: &'static str, args: &[&dyn std::fmt::Display]| i18n::format(lang, key, args);
+
+        // Завершение по сигналу: финальное сохранение и закрытие окна
+        if SHUTDOWN_REQUESTED.load(Ordering::SeqCst) {
+            self.flush_save();
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+        }
+
+        // Запоминание размера и положения окна; сохраняются при закрытии
+        let (inner_rect, outer_rect, minimized, close_requested) = ctx.input(|input| {
+            let viewport = input.viewport();
+            (viewport.inner_rect, viewport.outer_rect, viewport.minimized, viewport.close_requested())
+        });
+        if minimized != Some(true) {
+            if let Some(rect) = inner_rect {
+                self.window_size = Some([rect.width(), rect.height()]);
+            }
+            if let Some(rect) = outer_rect {
+                self.window_position = Some([rect.min.x, rect.min.y]);
+            }
+        }
+        // При запущенном помидоре закрытие откладывается до ответа в диалоге
+        if close_requested && self.active_timer.is_some() {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.confirm_quit = true;
+        } else if close_requested {
+            self.flush_save();
+        }
+
+        // Отмена, повтор (Ctrl+Z / Ctrl+Y) и смена темы, когда текстовое поле не в фокусе:
+        // внутри поля сочетания остаются за вводом текста
+        // (Ctrl+Shift+Z проверяется первым, иначе его перехватит Ctrl+Z)
+        if ctx.memory(|memory| memory.focused().is_none()) {
+            if ctx.input_mut(|i| i.consume_shortcut(&shortcuts::REDO_ALT) || i.consume_shortcut(&shortcuts::REDO)) {
+                self.redo();
+            }
+            if ctx.input_mut(|i| i.consume_shortcut(&shortcuts::UNDO)) {
+                self.undo();
+            }
+            // Смена темы (Ctrl+T)
+            if ctx.input_mut(|i| i.consume_shortcut(&shortcuts::TOGGLE_THEME)) {
+                self.toggle_theme();
+            }
+            // Переключение фильтра по статусу (Ctrl+Shift+F); проверяется до перехода к поиску,
+            // иначе его перехватит Ctrl+F
+            if ctx.input_mut(|i| i.consume_shortcut(&shortcuts::CYCLE_FILTER)) {
+                self.settings.status_filter = self.settings.status_filter.next();
+                self.autosave();
+            }
+        }
+
+        // Масштаб интерфейса: Ctrl+= / Ctrl+- меняют его на шаг, Ctrl+0 возвращает 100%.
+        // Встроенное масштабирование egui отключено, чтобы масштаб ограничивался и сохранялся.
+        ctx.options_mut(|options| options.zoom_with_keyboard = false);
+        let (zoom_in, zoom_out, zoom_reset) = ctx.input_mut(|i| {
+            (
+                i.consume_shortcut(&shortcuts::ZOOM_IN) || i.consume_shortcut(&shortcuts::ZOOM_IN_ALT),
+                i.consume_shortcut(&shortcuts::ZOOM_OUT),
+                i.consume_shortcut(&shortcuts::ZOOM_RESET),
+            )
+        });
+        if zoom_in || zoom_out || zoom_reset {
+            if zoom_reset {
+                self.settings.ui_scale = UiScale::default();
+            } else {
+                self.settings.ui_scale.bump(if zoom_in { 1.0 } else { -1.0 });
+            }
+            self.autosave();
+        }
+        // Множитель к системному масштабу, то есть pixels_per_point = системный * ui_scale
+        if ctx.zoom_factor() != self.settings.ui_scale.get() {
+            ctx.set_zoom_factor(self.settings.ui_scale.get());
+        }
+
+        // Переход к поиску (Ctrl+F) работает и из других полей: в тексте это сочетание ничего не делает
+        let focus_search = ctx.input_mut(|i| i.consume_shortcut(&shortcuts::FOCUS_SEARCH));
+        let shortcut = |shortcut: &egui::KeyboardShortcut| shortcuts::label(ctx, shortcut);
+
+        // Установка темы интерфейса
+        let mut visuals = match self.settings.theme {
+            Theme::Dark => egui::Visuals::dark(),
+            Theme::Light => egui::Visuals::light(),
+            Theme::HighContrast => high_contrast_visuals(),
+            // Если система не сообщает тему, используется темная
+            Theme::Auto => match ctx.system_theme() {
+                Some(egui::Theme::Light) => egui::Visuals::light(),
+                _ => egui::Visuals::dark(),
+            },
+        };
+        if let Some(accent) = self.accent_color() {
+            apply_accent(&mut visuals, accent);
+        }
+        ctx.set_style(egui::Style {
+            visuals,
+            ..Default::default()
+        });
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading(t("To-Do List"));
+
+            // Вкладки списков; открытый список можно переименовать или удалить
+            ui.horizontal_wrapped(|ui| {
+                let mut switch_to = None;
+                let mut rename_to = None;
+                for (index, list) in self.lists.iter().enumerate() {
+                    // Во время переименования вместо вкладки открытого списка стоит поле ввода
+                    if let Some(name) = self.list_rename.as_mut().filter(|_| index == self.current_list) {
+                        let response = ui.add(egui::TextEdit::singleline(name).desired_width(120.0));
+                        response.request_focus();
+                        if ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                            rename_to = Some(None);
+                        } else if response.lost_focus() {
+                            rename_to = Some(Some(name.clone()));
+                        }
+                        continue;
+                    }
+                    // У остальных списков рядом с именем число невыполненных задач
+                    let label = if index == self.current_list {
+                        list.name.clone()
+                    } else {
+                        format!("{} ({})", list.name, list.tasks.iter().filter(|task| !task.completed).count())
+                    };
+                    if ui.selectable_label(index == self.current_list, label).clicked() {
+                        switch_to = Some(index);
+                    }
+                }
+                if let Some(name) = rename_to {
+                    self.list_rename = None;
+                    if let Some(name) = name {
+                        self.rename_list(&name);
+                    }
+                }
+                if let Some(index) = switch_to {
+                    self.switch_list(index);
+                }
+
+                ui.separator();
+                if ui.button("➕").on_hover_text(t("New List")).clicked() {
+                    self.add_list();
+                }
+                if ui.button("✏").on_hover_text(t("Rename List")).clicked() {
+                    self.list_rename = Some(self.lists[self.current_list].name.clone());
+                }
+                let can_delete = self.lists.len() > 1;
+                let hint = if can_delete { t("Delete List") } else { t("The last list can't be deleted") };
+                if ui.add_enabled(can_delete, egui::Button::new("🗑")).on_hover_text(hint).on_disabled_hover_text(hint).clicked() {
+                    self.confirm_delete_list = true;
+                }
+            });
+            ui.separator();
+
+            // Предложение восстановить данные после аварийного завершения
+            if self.recovery_available {
+                ui.horizontal(|ui| {
+                    ui.colored_label(egui::Color32::YELLOW, t("The app crashed last time. Recover unsaved changes?"));
+                    if ui.button(t("Recover")).clicked() {
+                        self.recover_from_crash();
+                    }
+                    if ui.button(t("Discard")).clicked() {
+                        let _ = fs::remove_file(with_suffix(&self.save_path, RECOVERY_SUFFIX));
+                        self.recovery_available = false;
+                    }
+                });
+            }
+
+            // Нечитаемый файл задач сохранен под другим именем; сообщение висит, пока его не закроют
+            if let Some(corrupt_path) = &self.corrupt_file {
+                let message = tf(
+                    "{} could not be read. It was kept as {} so you can recover it manually.",
+                    &[&self.save_path.display(), &corrupt_path.display()],
+                );
+                let mut dismissed = false;
+                ui.horizontal_wrapped(|ui| {
+                    ui.colored_label(egui::Color32::YELLOW, message);
+                    dismissed = ui.button(t("Dismiss")).clicked();
+                });
+                if dismissed {
+                    self.corrupt_file = None;
+                }
+            }
+
+            // Напоминание о залежавшихся задачах; щелчок показывает только их
+            let stale_days = self.settings.stale_days.get();
+            if self.stale_filter {
+                ui.horizontal(|ui| {
+                    ui.label(tf("Showing tasks older than {} days", &[&stale_days]));
+                    if ui.button(t("Show All")).clicked() {
+                        self.stale_filter = false;
+                    }
+                });
+            } else if !self.stale_dismissed {
+                let stale = self.stale_tasks(stale_days).len();
+                if stale > 0 {
+                    ui.horizontal(|ui| {
+                        let message = tf("You have {} tasks older than {} days — review?", &[&stale, &stale_days]);
+                        if ui.link(message).on_hover_text(t("Show only these tasks")).clicked() {
+                            self.stale_filter = true;
+                        }
+                        if ui.button(t("Dismiss")).clicked() {
+                            self.stale_dismissed = true;
+                        }
+                    });
+                }
+            }
+
+            // Ошибка сохранения остается на экране до следующего успешного сохранения
+            if let Some(error) = self.save_error.clone() {
+                ui.horizontal(|ui| {
+                    ui.colored_label(egui::Color32::RED, error);
+                    if ui.button(t("Retry")).clicked() {
+                        self.flush_save();
+                    }
+                });
+            }
+
+            // Сообщение об ошибке работы с файлами
+            if let Some(error) = &self.last_error {
+                ui.colored_label(egui::Color32::RED, error);
+            }
+
+            // Отображение текущего времени в правом верхнем углу
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
+                ui.label(self.current_time());
+            });
+
+            ui.horizontal(|ui| {
+                // Кнопка для смены темы
+                if ui.button(t("Toggle Theme")).on_hover_text(tf("Switch to the next theme ({})", &[&shortcut(&shortcuts::TOGGLE_THEME)])).clicked() {
+                    self.toggle_theme();
+                }
+
+                // Ручное сохранение; точка показывает, что есть несохраненные изменения
+                if self.settings.manual_save {
+                    if ui.add_enabled(self.dirty, egui::Button::new(t("Save Now"))).clicked() {
+                        self.flush_save();
+                    }
+                    if self.dirty {
+                        ui.colored_label(egui::Color32::YELLOW, "●").on_hover_text(t("Unsaved changes"));
+                    }
+                }
+            });
+
+            // Настройки
+            egui::CollapsingHeader::new(t("Settings")).show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(t("Progress:"));
+                    let bar = ui.radio_value(&mut self.settings.progress_style, ProgressStyle::Bar, t("Bar"));
+                    let ring = ui.radio_value(&mut self.settings.progress_style, ProgressStyle::Ring, t("Ring"));
+                    if bar.changed() || ring.changed() {
+                        self.autosave();
+                    }
+                });
+
+                // Язык интерфейса
+                ui.horizontal(|ui| {
+                    ui.label(t("Language:"));
+                    egui::ComboBox::from_id_salt("language")
+                        .selected_text(self.settings.language.label())
+                        .show_ui(ui, |ui| {
+                            for language in Lang::ALL {
+                                if ui.selectable_value(&mut self.settings.language, language, language.label()).changed() {
+                                    self.autosave();
+                                }
+                            }
+                        });
+                });
+
+                if ui.checkbox(&mut self.settings.activity_log, t("Activity Log"))
+                    .on_hover_text(tf("Append every change to {}", &[&self.sibling_path(ACTIVITY_LOG).display()]))
+                    .changed()
+                {
+                    self.autosave();
+                }
+
+                if ui.checkbox(&mut self.settings.note_on_complete, t("Ask for a Note on Completion")).changed() {
+                    self.autosave();
+                }
+
+                if ui.checkbox(&mut self.settings.focus_today, t("Open with Today's Tasks"))
+                    .on_hover_text(t("Show tasks due today and overdue ones on launch, or all tasks if there are none"))
+                    .changed()
+                {
+                    self.autosave();
+                }
+
+                if ui.checkbox(&mut self.settings.global_search, t("Share Search Between Lists"))
+                    .on_hover_text(t("Keep the search when switching lists instead of remembering one per list"))
+                    .changed()
+                {
+                    self.autosave();
+                }
+
+                if ui.checkbox(&mut self.settings.mute_notifications, t("Mute Overdue Notifications"))
+                    .on_hover_text(t("Don't show a desktop notification when a task becomes overdue"))
+                    .changed()
+                {
+                    self.autosave();
+                }
+
+                if ui.checkbox(&mut self.settings.manual_save, t("Save Manually"))
+                    .on_hover_text(t("Write the task file only with \"Save Now\" and on exit"))
+                    .changed()
+                {
+                    self.autosave();
+                    if !self.settings.manual_save {
+                        self.flush_save(); // Накопленные изменения записываются сразу
+                    }
+                }
+
+                // Промежуток автосохранения; при ручном сохранении не используется
+                ui.add_enabled_ui(!self.settings.manual_save, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label(t("Autosave Every:"));
+                        let mut seconds = self.settings.save_interval.get().as_secs();
+                        let response = ui.add(egui::Slider::new(&mut seconds, SaveInterval::RANGE).suffix(" s"))
+                            .on_hover_text(t("A longer interval writes less often, but more changes can be lost in a crash"));
+                        if response.changed() {
+                            self.settings.save_interval = SaveInterval(seconds);
+                            self.autosave();
+                        }
+                    });
+                });
+
+                if ui.checkbox(&mut self.settings.weight_subtasks, t("Count Subtasks in Progress"))
+                    .on_hover_text(t("Partially done tasks add to the progress by their finished subtasks"))
+                    .changed()
+                {
+                    self.autosave();
+                }
+
+                // Формат часов: готовый вариант или своя строка strftime
+                ui.horizontal(|ui| {
+                    ui.label(t("Clock Format:"));
+                    let preset = TimeFormat::PRESETS.iter().find(|(_, format)| *format == self.settings.time_format.0);
+                    egui::ComboBox::from_id_salt("time_format")
+                        .selected_text(preset.map_or(t("Custom"), |(name, _)| t(name)))
+                        .show_ui(ui, |ui| {
+                            for (name, format) in TimeFormat::PRESETS {
+                                if ui.selectable_label(self.settings.time_format.0 == format, t(name)).clicked() {
+                                    self.settings.time_format = TimeFormat(format.to_string());
+                                    self.autosave();
+                                }
+                            }
+                        });
+                    let valid = self.settings.time_format.is_valid();
+                    let mut editor = egui::TextEdit::singleline(&mut self.settings.time_format.0).desired_width(140.0);
+                    if !valid {
+                        editor = editor.text_color(egui::Color32::RED);
+                    }
+                    let response = ui.add(editor).on_hover_text(t("chrono strftime format, e.g. %H:%M"));
+                    if response.lost_focus() {
+                        self.autosave();
+                    }
+                });
+
+                // Час смены дня: выполненное ночью до этого часа относится к предыдущему дню
+                ui.horizontal(|ui| {
+                    ui.label(t("New Day Starts At:"));
+                    let response = ui.add(egui::Slider::new(&mut self.settings.day_rollover_hour, 0..=23).suffix(":00"))
+                        .on_hover_text(t("Tasks completed after midnight but before this hour count for the previous day"));
+                    if response.changed() {
+                        self.autosave();
+                    }
+                });
+
+                // Возраст, после которого невыполненные задачи попадают в напоминание
+                ui.horizontal(|ui| {
+                    ui.label(t("Remind About Tasks Older Than:"));
+                    let mut days = self.settings.stale_days.get();
+                    let response = ui.add(egui::Slider::new(&mut days, StaleDays::RANGE).suffix(format!(" {}", t("days"))));
+                    if response.changed() {
+                        self.settings.stale_days = StaleDays(days);
+                        self.autosave();
+                    }
+                });
+
+                // Ограничение длины описания задачи
+                ui.horizontal(|ui| {
+                    ui.label(t("Max Task Length:"));
+                    let response = ui.add(egui::DragValue::new(&mut self.settings.max_task_len)
+                        .range(0..=10_000)
+                        .custom_formatter(|value, _| if value == 0.0 { t("no limit").to_string() } else { format!("{}", value) })
+                    );
+                    if response.changed() {
+                        self.autosave();
+                    }
+                });
+
+                // Цвет кнопок, выделения и индикатора прогресса
+                ui.horizontal(|ui| {
+                    ui.label(t("Accent Color:"));
+                    let mut color = self.settings.accent.unwrap_or_else(|| {
+                        let default = ui.visuals().selection.bg_fill;
+                        [default.r(), default.g(), default.b()]
+                    });
+                    if ui.color_edit_button_srgb(&mut color).changed() {
+                        self.settings.accent = Some(color);
+                        self.autosave();
+                    }
+                    if ui.add_enabled(self.settings.accent.is_some(), egui::Button::new(t("Reset")))
+                        .on_hover_text(t("Use the theme's accent color"))
+                        .clicked()
+                    {
+                        self.settings.accent = None;
+                        self.autosave();
+                    }
+                });
+
+                // Шаблоны задач: правка на месте и удаление
+                ui.label(t("Templates:"));
+                if self.templates.is_empty() {
+                    ui.label(egui::RichText::new(t("Save the task input as a template to reuse it")).weak());
+                }
+                let mut removed = None;
+                let mut edited = false;
+                for (n, template) in self.templates.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        edited |= ui.add(egui::TextEdit::multiline(template).desired_rows(1).desired_width(240.0)).changed();
+                        if ui.small_button("✖").on_hover_text(t("Remove Template")).clicked() {
+                            removed = Some(n);
+                        }
+                    });
+                }
+                if let Some(n) = removed {
+                    self.templates.remove(n);
+                }
+                if edited || removed.is_some() {
+                    self.autosave();
+                }
+
+                // Масштаб интерфейса; применяется после отпускания ползунка, чтобы он не уезжал из-под курсора
+                ui.horizontal(|ui| {
+                    ui.label(t("UI Scale:"));
+                    let mut scale = self.settings.ui_scale.get();
+                    let response = ui.add(egui::Slider::new(&mut scale, UiScale::RANGE)
+                        .step_by(UiScale::STEP as f64)
+                        .custom_formatter(|value, _| format!("{:.0}%", value * 100.0)))
+                        .on_hover_text(tf("{} / {} to zoom, {} to reset", &[
+                            &shortcut(&shortcuts::ZOOM_IN),
+                            &shortcut(&shortcuts::ZOOM_OUT),
+                            &shortcut(&shortcuts::ZOOM_RESET),
+                        ]));
+                    if response.changed() {
+                        self.settings.ui_scale = UiScale(scale);
+                        self.autosave();
+                    }
+                });
+
+                if ui.button(t("Restore Defaults")).on_hover_text(t("Reset all settings without touching tasks")).clicked() {
+                    self.confirm_reset = true;
+                }
+            });
+
+            ui.horizontal(|ui| {
+                let percent = self.progress();
+                let progress = percent / 100.0;
+                ui.label(tf("Progress: {}%", &[&format!("{:.2}", percent)]));
+                match self.settings.progress_style {
+                    // Полоса прогресса с анимацией, цвет зависит от доли выполненного
+                    ProgressStyle::Bar => {
+                        let color = if percent < PROGRESS_LOW {
+                            PROGRESS_COLORS[0]
+                        } else if percent <= PROGRESS_HIGH {
+                            PROGRESS_COLORS[1]
+                        } else {
+                            PROGRESS_COLORS[2]
+                        };
+                        ui.add(egui::ProgressBar::new(progress)
+                            .animate(true)  // Включаем анимацию
+                            .desired_width(300.0)
+                            .fill(color));
+                    }
+                    // Компактное кольцо прогресса
+                    ProgressStyle::Ring => progress_ring(ui, progress),
+                }
+                if !self.tasks().is_empty() && progress >= 1.0 {
+                    ui.label(t("🎉 All done!"));
+                }
+            });
+
+            // Запущенный помидор: обратный отсчет и полоса прогресса
+            if let Some((index, started)) = self.active_timer {
+                let remaining = POMODORO.saturating_sub(started.elapsed());
+                ui.horizontal(|ui| {
+                    ui.label(format!("🍅 {:02}:{:02}", remaining.as_secs() / 60, remaining.as_secs() % 60));
+                    let done = 1.0 - remaining.as_secs_f32() / POMODORO.as_secs_f32();
+                    ui.add(egui::ProgressBar::new(done).desired_width(200.0));
+                    ui.label(egui::RichText::new(&self.tasks()[index].description).weak());
+                    if ui.button(t("Stop")).clicked() {
+                        self.stop_timer_and_save();
+                    }
+                });
+            }
+            // Сообщение об окончании помидора мигает несколько секунд
+            if let Some((description, finished)) = &self.finished_pomodoro {
+                if finished.elapsed() < POMODORO_NOTICE {
+                    let visible = finished.elapsed().as_millis() / 500 % 2 == 0;
+                    let color = if visible { egui::Color32::from_rgb(230, 80, 60) } else { egui::Color32::TRANSPARENT };
+                    ui.colored_label(color, tf("🍅 Pomodoro finished: {}", &[description]));
+                } else {
+                    self.finished_pomodoro = None;
+                }
+            }
+
+            // Статистика списка, пересчитывается каждый кадр
+            egui::CollapsingHeader::new(t("Statistics")).show(ui, |ui| {
+                let today = self.today();
+                let rollover_hour = self.settings.day_rollover_hour;
+                let overdue = self.tasks().iter().filter(|task| task.is_overdue(today)).count();
+                let completed_today = self.tasks().iter()
+                    .filter(|task| task.completed_day(rollover_hour) == Some(today))
+                    .count();
+                egui::Grid::new("statistics").num_columns(2).show(ui, |ui| {
+                    ui.label(t("Total tasks:"));
+                    ui.label(self.tasks().len().to_string());
+                    ui.end_row();
+                    ui.label(t("Completed:"));
+                    ui.label(format!("{} ({:.0}%)", self.completed_tasks(), self.progress()));
+                    ui.end_row();
+                    ui.label(t("Overdue:"));
+                    ui.label(overdue.to_string());
+                    ui.end_row();
+                    ui.label(t("Completed today:"));
+                    ui.label(completed_today.to_string());
+                    ui.end_row();
+                });
+
+                // Выполненные задачи по дням за последнюю неделю
+                ui.label(tf("Completed in the last {} days:", &[&CHART_DAYS]));
+                let days: Vec<(NaiveDate, usize)> = (0..CHART_DAYS).rev()
+                    .map(|ago| today - chrono::Duration::days(ago))
+                    .map(|day| {
+                        let count = self.tasks().iter()
+                            .filter(|task| task.completed_day(rollover_hour) == Some(day))
+                            .count();
+                        (day, count)
+                    })
+                    .collect();
+                completion_chart(ui, &days, |day, count| tf("{}: {} completed", &[&day.format("%Y-%m-%d"), &count]));
+            });
+
+            ui.separator();
+
+            // Поле для ввода новой задачи
+            ui.vertical(|ui| {
+                // Ctrl+Enter перехватывается до поля ввода, чтобы не вставлять перевод строки
+                let input_id = egui::Id::new("new_task_input");
+                let submitted = ui.memory(|memory| memory.has_focus(input_id))
+                    && ui.input_mut(|i| i.consume_shortcut(&shortcuts::ADD_TASK));
+                let add_shortcut = shortcut(&shortcuts::ADD_TASK);
+
+                let response = ui.add(egui::TextEdit::multiline(&mut self.new_task)
+                    .id(input_id)
+                    .hint_text(tf("Enter a new task... ({} to add)", &[&add_shortcut]))  // Подсказка
+                    .desired_rows(3)                 // Количество строк
+                    .desired_width(300.0)            // Ширина поля
+                );
+
+                // Счетчик символов и строк; красный, если описание слишком длинное
+                let chars = self.new_task.chars().count();
+                let lines = self.new_task.lines().count().max(1);
+                let too_long = self.exceeds_max_len(self.new_task.trim());
+                let counter = match self.settings.max_task_len {
+                    0 => tf("{} characters, {} lines", &[&chars, &lines]),
+                    limit => tf("{}/{} characters, {} lines", &[&chars, &limit, &lines]),
+                };
+                let color = if too_long { egui::Color32::RED } else { egui::Color32::from_gray(120) };
+                ui.label(egui::RichText::new(counter).small().color(color));
+                ui.label(egui::RichText::new(t("Quick add: !high or !low sets the priority, @YYYY-MM-DD the due date, #tag adds a tag"))
+                    .small()
+                    .weak());
+
+                // Предупреждение о повторе снимается, как только текст меняется
+                if response.changed() {
+                    self.confirm_duplicate = false;
+                }
+
+                // Кнопка добавления задачи; задачу-повтор нужно подтвердить повторным нажатием
+                let add_label = if self.confirm_duplicate { t("Add Anyway") } else { t("Add Task") };
+                let mut clicked = false;
+                ui.horizontal(|ui| {
+                    clicked = ui.add_enabled(!too_long, egui::Button::new(add_label))
+                        .on_hover_text(tf("Add Task ({})", &[&add_shortcut]))
+                        .clicked();
+
+                    // Шаблон подставляется в поле ввода, чтобы его можно было поправить перед добавлением
+                    let mut chosen = None;
+                    ui.add_enabled_ui(!self.templates.is_empty(), |ui| {
+                        egui::ComboBox::from_id_salt("templates")
+                            .selected_text(t("Templates"))
+                            .show_ui(ui, |ui| {
+                                for template in &self.templates {
+                                    let title = template.lines().next().unwrap_or_default();
+                                    if ui.selectable_label(false, title).on_hover_text(template).clicked() {
+                                        chosen = Some(template.clone());
+                                    }
+                                }
+                            });
+                    });
+                    if let Some(template) = chosen {
+                        self.new_task = template;
+                        self.confirm_duplicate = false;
+                        ui.memory_mut(|memory| memory.request_focus(input_id));
+                    }
+
+                    let input = self.new_task.trim();
+                    let can_save = !input.is_empty() && !self.templates.iter().any(|template| template == input);
+                    if ui.add_enabled(can_save, egui::Button::new(t("Save as Template")))
+                        .on_hover_text(t("Save the current input as a template"))
+                        .clicked()
+                    {
+                        self.templates.push(input.to_string());
+                        self.autosave();
+                    }
+                });
+                if clicked || submitted {
+                    if !self.confirm_duplicate && self.is_duplicate(&parse_quick_add(&self.new_task).description, None) {
+                        self.confirm_duplicate = true;
+                    } else if self.add_task_from_input() {
+                        self.confirm_duplicate = false;
+                        response.request_focus(); // Возвращаем фокус для ввода следующей задачи
+                    }
+                }
+                if self.confirm_duplicate {
+                    ui.colored_label(egui::Color32::from_rgb(230, 160, 40), t("⚠ This task is already in the list. Add it anyway?"));
+                }
+            });
+
+            ui.separator();
+
+            // Черновик для быстрых заметок
+            egui::CollapsingHeader::new(t("Scratchpad")).show(ui, |ui| {
+                let response = ui.add(egui::TextEdit::multiline(&mut self.scratchpad)
+                    .hint_text(t("Jot something down..."))
+                    .desired_rows(3)
+                    .desired_width(300.0)
+                );
+                if response.lost_focus() {
+                    self.autosave(); // Автосохранение
+                }
+
+                // Перенос текста черновика в новую задачу
+                let has_text = !self.scratchpad.trim().is_empty();
+                if ui.add_enabled(has_text, egui::Button::new(t("Convert to Task"))).clicked() {
+                    self.record_undo();
+                    let task = Task::new(self.scratchpad.trim().to_string());
+                    self.tasks_mut().push(task);
+                    self.log_action(&format!("add: {}", self.scratchpad.trim()));
+                    self.scratchpad.clear();
+                    self.autosave(); // Автосохранение
+                }
+            });
+
+            ui.separator();
+
+            // Поисковая строка
+            ui.horizontal(|ui| {
+                ui.label(t("Search:"));
+                let response = ui.add(egui::TextEdit::singleline(&mut self.search_query).hint_text(shortcut(&shortcuts::FOCUS_SEARCH)))
+                    .on_hover_text(t("Narrow down with priority:high, tag:home or due:today (also tomorrow, overdue, none or YYYY-MM-DD)"));
+                if focus_search {
+                    response.request_focus();
+                }
+                if ui.add_enabled(!self.search_query.is_empty(), egui::Button::new("✖").small())
+                    .on_hover_text(t("Clear Search"))
+                    .clicked()
+                {
+                    self.search_query.clear();
+                }
+
+                // Escape в поле поиска очищает запрос (поле при этом теряет фокус);
+                // если запрос уже пуст, выходим из режима одной задачи
+                if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                    if self.search_query.is_empty() {
+                        self.solo_task = None;
+                    }
+                    self.search_query.clear();
+                }
+
+                // Нечеткий поиск допускает опечатки и слова в другом порядке
+                if ui.checkbox(&mut self.settings.fuzzy_search, t("Fuzzy"))
+                    .on_hover_text(t("Allow typos and words in any order"))
+                    .changed()
+                {
+                    self.autosave();
+                }
+
+                // Порядок сортировки
+                let mut sort_changed = false;
+                egui::ComboBox::from_id_salt("sort_mode")
+                    .selected_text(tf("Sort: {}", &[&t(self.settings.sort_mode.label())]))
+                    .show_ui(ui, |ui| {
+                        for mode in SortMode::ALL {
+                            sort_changed |= ui.selectable_value(&mut self.settings.sort_mode, mode, t(mode.label())).changed();
+                        }
+                    });
+                let (arrow, hint) = if self.settings.sort_descending {
+                    ("⬇", t("Descending"))
+                } else {
+                    ("⬆", t("Ascending"))
+                };
+                if ui.button(arrow).on_hover_text(hint).clicked() {
+                    self.settings.sort_descending = !self.settings.sort_descending;
+                    sort_changed = true;
+                }
+                if sort_changed {
+                    self.autosave();
+                }
+            });
+
+            // Фильтр по статусу выполнения с числом задач для каждого варианта
+            ui.horizontal(|ui| {
+                let counts = self.status_counts();
+                for (n, filter) in StatusFilter::ALL.into_iter().enumerate() {
+                    if n > 0 {
+                        ui.label(egui::RichText::new("•").weak());
+                    }
+                    let selected = self.settings.status_filter == filter;
+                    let label = format!("{} {}", t(filter.label()), counts[n]);
+                    let response = ui.selectable_label(selected, label)
+                        .on_hover_text(tf("Press {} to switch filters", &[&shortcut(&shortcuts::CYCLE_FILTER)]));
+                    if response.clicked() && !selected {
+                        self.settings.status_filter = filter;
+                        self.autosave();
+                    }
+                }
+                // При поиске или фильтре по тегу и цвету числа считаются только по подходящим задачам
+                let narrowed = !self.search_query.trim().is_empty() || self.tag_filter.is_some() || self.color_filter.is_some()
+                    || self.stale_filter;
+                if narrowed {
+                    let matching = tf("matching: {} of {}", &[&counts[0], &self.tasks().len()]);
+                    ui.label(egui::RichText::new(matching).weak());
+                }
+            });
+
+            // Период выполнения для фильтра "Выполненные"
+            if self.settings.status_filter == StatusFilter::Completed && self.has_completion_times() {
+                ui.horizontal(|ui| {
+                    ui.label(t("Completed:"));
+                    for within in CompletedWithin::ALL {
+                        if ui.selectable_value(&mut self.settings.completed_within, within, t(within.label())).changed() {
+                            self.autosave();
+                        }
+                    }
+                });
+            }
+
+            // Фильтр по тегу; повторный щелчок по выбранному тегу снимает фильтр
+            let tags = self.all_tags();
+            if self.tag_filter.as_ref().is_some_and(|tag| !tags.contains(tag)) {
+                self.tag_filter = None; // Тег пропал, например, после отмены
+            }
+            if !tags.is_empty() {
+                ui.horizontal_wrapped(|ui| {
+                    ui.label(t("Tags:"));
+                    for tag in tags {
+                        let selected = self.tag_filter.as_ref() == Some(&tag);
+                        if ui.selectable_label(selected, &tag).clicked() {
+                            self.tag_filter = if selected { None } else { Some(tag) };
+                            self.autosave();
+                        }
+                    }
+                });
+            }
+
+            // Легенда цветов: щелчок по образцу показывает только задачи этого цвета
+            let colors = self.all_colors();
+            if self.color_filter.is_some_and(|color| !colors.contains(&color)) {
+                self.color_filter = None;
+            }
+            if !colors.is_empty() {
+                ui.horizontal_wrapped(|ui| {
+                    ui.label(t("Colors:"));
+                    for color in colors {
+                        let selected = self.color_filter == Some(color);
+                        if color_swatch(ui, color, selected).on_hover_text(t("Show tasks with this color")).clicked() {
+                            self.color_filter = if selected { None } else { Some(color) };
+                            self.autosave();
+                        }
+                    }
+                });
+            }
+
+            // Групповые действия с отмеченными задачами
+            if !self.marked.is_empty() {
+                ui.horizontal(|ui| {
+                    ui.label(tf("{} selected", &[&self.marked.len()]));
+                    if ui.button(t("Complete Selected")).clicked() {
+                        self.complete_marked();
+                    }
+                    if ui.button(t("Delete Selected")).clicked() {
+                        self.confirm_delete_marked = true;
+                    }
+                    egui::ComboBox::from_id_salt("bulk_priority")
+                        .selected_text(t(self.bulk_priority.label()))
+                        .width(80.0)
+                        .show_ui(ui, |ui| {
+                            for priority in Priority::ALL {
+                                ui.selectable_value(&mut self.bulk_priority, priority, t(priority.label()));
+                            }
+                        });
+                    if ui.button(t("Apply")).on_hover_text(t("Set this priority for the selected tasks")).clicked() {
+                        self.set_marked_priority(self.bulk_priority);
+                    }
+                    if ui.button(t("Clear Selection")).clicked() {
+                        self.marked.clear();
+                    }
+                });
+            }
+
+            // Выход из режима одной задачи
+            if self.solo_task.is_some() && ui.button(t("Show All")).on_hover_text(t("Show all tasks again")).clicked() {
+                self.solo_task = None;
+            }
+
+            // Список задач с фильтрацией. Между кадрами прокрутку хранит egui (у каждого списка
+            // своя), а сохраненная в файле применяется один раз после запуска.
+            let mut scroll_area = egui::ScrollArea::vertical().id_salt(("task_list", self.current_list));
+            if !self.scroll_restored {
+                scroll_area = scroll_area.vertical_scroll_offset(self.scroll_offset);
+                self.scroll_restored = true;
+            }
+            let scroll = scroll_area.show(ui, |ui| {
+                let mut delete_request = None;
+                let mut edit_task = None;
+                let mut finished_edit = None;
+                let mut cancelled_edit = false;
+                let mut icon_request = None;
+                let mut priority_request = None;
+                let mut due_edit = None;
+                let mut due_finished = false;
+                let mut due_preset = None;
+                let mut snooze_request = None;
+                let task_dir = self.task_dir();
+                let today = self.today();
+                let rollover_hour = self.settings.day_rollover_hour;
+                let mut solo_task = None;
+                let mut toggled = None;
+                let mut edited = false;
+                let mut move_request = None;
+                let mut tag_clicked = None;
+                let mut mark_toggled = None;
+                let mut duplicate_request = None;
+                let char_limit = self.char_limit();
+                let mut pin_toggled = None;
+                let mut notes_toggled = None;
+                let mut timer_request = None;
+                let mut reorder_request = None;
+                let mut color_request = None;
+                let mut notes_edit = None;
+                let mut notes_finished = false;
+                let mut subtask_added = None;
+                let mut subtask_toggled = None;
+                let mut subtask_removed = None;
+                let mut subtask_focused = false;
+                let mut subtask_edited = None;
+                let mut tag_added = None;
+                let mut tag_removed = None;
+
+                // Индексы задач для редактирования или удаления
+                // (в режиме одной задачи фильтры не применяются)
+                let task_indices: Vec<usize> = match self.solo_task {
+                    Some(solo) => vec![solo],
+                    None => self.visible_task_indices(),
+                };
+
+                // Подсказка вместо пустого списка
+                if task_indices.is_empty() {
+                    let message = if self.tasks().is_empty() {
+                        t("No tasks yet — add one above!")
+                    } else {
+                        t("No tasks match the current filters.")
+                    };
+                    ui.add_space(20.0);
+                    ui.vertical_centered(|ui| ui.label(egui::RichText::new(message).weak()));
+                }
+
+                // Выполненные задачи выводятся отдельной сворачиваемой группой (кроме режима одной задачи)
+                let completed_count = self.completed_tasks();
+                let (mut completed_indices, active_indices): (Vec<usize>, Vec<usize>) = match self.solo_task {
+                    Some(_) => (Vec::new(), task_indices),
+                    None => task_indices.into_iter().partition(|&i| self.tasks()[i].completed),
+                };
+                if self.settings.completed_by_time {
+                    self.sort_by_completion(&mut completed_indices);
+                }
+
+                // Соседи задачи для кнопок ▲/▼: только в ручном порядке и среди видимых задач
+                // той же группы (активные или выполненные, закрепленные или нет).
+                // Выполненные задачи, упорядоченные по времени, не переставляются.
+                let mut neighbors = std::collections::HashMap::new();
+                if self.settings.sort_mode == SortMode::Manual && self.solo_task.is_none() {
+                    let completed_group = if self.settings.completed_by_time { &[][..] } else { &completed_indices[..] };
+                    for group in [&active_indices[..], completed_group] {
+                        for (n, &i) in group.iter().enumerate() {
+                            let same_pin = |j: &usize| self.tasks()[*j].pinned == self.tasks()[i].pinned;
+                            let previous = n.checked_sub(1).map(|m| group[m]).filter(same_pin);
+                            let next = group.get(n + 1).copied().filter(same_pin);
+                            neighbors.insert(i, (previous, next));
+                        }
+                    }
+                }
+
+                // Отображение одной строки задачи
+                let history = self.completion_history();
+                let mut completed_by_time = self.settings.completed_by_time;
+                let mut show_row = |ui: &mut egui::Ui, i: usize| {
+                    let done_before = self.done_before(i, &history);
+                    // Повтор показывается только для задачи, которую сейчас редактируют
+                    let duplicate = self.selected_task == Some(i) && self.is_duplicate(&self.tasks()[i].description, Some(i));
+                    let task = &mut self.lists[self.current_list].tasks[i];
+                    let overdue = task.is_overdue(today);
+                    let marked = self.marked.contains(&i);
+                    // Место под фон строки, который рисуется после того, как известен ее размер
+                    let background = ui.painter().add(egui::Shape::Noop);
+                    let row = ui.horizontal(|ui| {
+                        // Отметка задачи для групповых действий
+                        let mark_label = if marked { "☑" } else { "☐" };
+                        if ui.selectable_label(marked, mark_label).on_hover_text(t("Select for Bulk Actions")).clicked() {
+                            mark_toggled = Some(i);
+                        }
+
+                        // Чекбокс выполнения задачи
+                        // (изменение применяется после цикла, чтобы сохранить состояние для отмены)
+                        let mut completed = task.completed;
+                        if ui.checkbox(&mut completed, "").changed() {
+                            toggled = Some((i, completed)); // Отслеживаем изменения
+                        }
+
+                        // Цветной индикатор приоритета
+                        let (dot, _) = ui.allocate_exact_size(egui::vec2(10.0, 10.0), egui::Sense::hover());
+                        ui.painter().circle_filled(dot.center(), 4.0, task.priority.color());
+
+                        // Значок задачи с меню выбора
+                        let icon_label = task.icon.as_deref().unwrap_or("🙂");
+                        let icon_response = ui.button(icon_label).on_hover_text(t("Choose Icon"));
+                        let popup_id = ui.make_persistent_id(("icon_picker", i));
+                        if icon_response.clicked() {
+                            ui.memory_mut(|memory| memory.toggle_popup(popup_id));
+                        }
+                        egui::popup::popup_below_widget(
+                            ui,
+                            popup_id,
+                            &icon_response,
+                            egui::PopupCloseBehavior::CloseOnClick,
+                            |ui| {
+                                egui::Grid::new(("icon_grid", i)).show(ui, |ui| {
+                                    for (n, icon) in TASK_ICONS.iter().enumerate() {
+                                        if ui.button(*icon).clicked() {
+                                            icon_request = Some((i, Some(icon.to_string())));
+                                        }
+                                        if n % 4 == 3 {
+                                            ui.end_row();
+                                        }
+                                    }
+                                });
+                                if ui.button(t("No Icon")).clicked() {
+                                    icon_request = Some((i, None));
+                                }
+                            },
+                        );
+
+                        // Описание выбранной задачи редактируется в поле, которое раскрывается
+                        let expanded = self.selected_task == Some(i) || self.solo_task == Some(i);
+                        let rows = if expanded { 3 } else { 1 };
+                        let text_color = if task.completed {
+                            egui::Color32::from_gray(120) // Серый цвет для выполненных задач
+                        } else {
+                            ui.visuals().override_text_color.unwrap_or(ui.visuals().widgets.inactive.text_color())
+                        };
+                        // Совпадения с поисковым запросом подсвечиваются
+                        let query = &SearchQuery::parse(&self.search_query).text;
+                        let fuzzy = self.settings.fuzzy_search;
+                        let mut layouter = |ui: &egui::Ui, text: &str, wrap_width: f32| {
+                            let font_id = egui::TextStyle::Body.resolve(ui.style());
+                            let mut job = highlight_matches(text, &search_ranges(text, query, fuzzy), font_id, text_color);
+                            job.wrap.max_width = wrap_width;
+                            ui.fonts(|fonts| fonts.layout_job(job))
+                        };
+                        // Вне редактирования описание показывается текстом, который переносится
+                        // по ширине колонки (длинные слова разбиваются), а строка растет по высоте.
+                        // Ссылки кликабельны; двойной щелчок по тексту начинает правку, а одиночный
+                        // отмечает задачу для групповых действий (двойной щелчок отметку не меняет).
+                        if self.selected_task != Some(i) {
+                            let font_id = egui::TextStyle::Body.resolve(ui.style());
+                            let description = &task.description;
+                            let task_completed = task.completed; // Выполненные задачи серые и зачеркнутые
+                            let links = url_ranges(description);
+                            let highlights = search_ranges(description, query, fuzzy);
+                            let mut start_editing = false;
+                            let mut toggle_mark = false;
+                            ui.allocate_ui(egui::vec2(DESCRIPTION_WIDTH, 0.0), |ui| {
+                                ui.set_min_width(DESCRIPTION_WIDTH);
+                                ui.horizontal_wrapped(|ui| {
+                                    ui.spacing_mut().item_spacing.x = 0.0;
+                                    if description.trim().is_empty() {
+                                        let placeholder = egui::Label::new(egui::RichText::new(t("Double-click to edit")).weak())
+                                            .sense(egui::Sense::click());
+                                        start_editing |= ui.add(placeholder).double_clicked();
+                                    }
+                                    let mut position = 0;
+                                    for link in links.iter().cloned().chain(std::iter::once(description.len()..description.len())) {
+                                        let text = &description[position..link.start];
+                                        if !text.is_empty() {
+                                            // Подсветка, попавшая в отрезок между ссылками, со смещением к его началу
+                                            let segment: Vec<_> = highlights.iter()
+                                                .filter(|range| range.start < link.start && range.end > position)
+                                                .map(|range| range.start.max(position) - position..range.end.min(link.start) - position)
+                                                .collect();
+                                            let mut job = highlight_matches(text, &segment, font_id.clone(), text_color);
+                                            if task_completed {
+                                                for section in &mut job.sections {
+                                                    section.format.strikethrough = egui::Stroke::new(1.0, text_color);
+                                                }
+                                            }
+                                            let label = egui::Label::new(job).wrap().sense(egui::Sense::click());
+                                            let response = ui.add(label).on_hover_text(t("Double-click to edit, click to select"));
+                                            start_editing |= response.double_clicked();
+                                            toggle_mark |= response.clicked();
+                                        }
+                                        if !link.is_empty() {
+                                            let url = &description[link.clone()];
+                                            let mut link_text = egui::RichText::new(url);
+                                            if task_completed {
+                                                link_text = link_text.strikethrough();
+                                            }
+                                            ui.hyperlink_to(link_text, url);
+                                        }
+                                        position = link.end;
+                                    }
+                                });
+                            });
+                            if start_editing {
+                                edit_task = Some(i);
+                                self.focus_description = Some(i);
+                            }
+                            if toggle_mark {
+                                mark_toggled = Some(i);
+                            }
+                        } else {
+                            let mut editor = egui::TextEdit::multiline(&mut task.description)
+                                .char_limit(char_limit)
+                                .desired_rows(rows)
+                                .desired_width(DESCRIPTION_WIDTH)
+                                .text_color(text_color);
+                            if !query.is_empty() {
+                                editor = editor.layouter(&mut layouter);
+                            }
+                            let response = ui.add(editor);
+                            if self.focus_description == Some(i) {
+                                response.request_focus();
+                                self.focus_description = None;
+                            }
+                            if response.gained_focus() {
+                                edit_task = Some(i);
+                            }
+                            if response.changed() {
+                                task.touch();
+                                edited = true;
+                            }
+                            if response.lost_focus() {
+                                finished_edit = Some(i);
+                                // Escape снимает фокус с поля и отменяет правку
+                                cancelled_edit = ui.input(|input| input.key_pressed(egui::Key::Escape));
+                            }
+                        }
+
+                        // Кнопка раскрытия заметок; в подсказке виден их текст
+                        let notes_visible = self.notes_open.contains(&i);
+                        let chevron = if notes_visible { "⏷" } else { "⏵" };
+                        let notes_hint = if task.notes.is_empty() { t("Notes") } else { task.notes.as_str() };
+                        let mut chevron_text = egui::RichText::new(chevron);
+                        if task.notes.is_empty() {
+                            chevron_text = chevron_text.weak();
+                        }
+                        if ui.add(egui::Button::new(chevron_text).frame(false)).on_hover_text(notes_hint).clicked() {
+                            notes_toggled = Some(i);
+                        }
+
+                        // Заметка о выполнении во всплывающей подсказке
+                        if let Some(note) = task.completion_note.as_ref().filter(|_| task.completed) {
+                            ui.label("📝").on_hover_text(note);
+                        }
+
+                        // Цвет метки с меню выбора
+                        let color_response = match task.color {
+                            Some(color) => color_swatch(ui, color, false),
+                            None => ui.button("🎨"),
+                        }
+                        .on_hover_text(t("Choose Color"));
+                        let color_popup_id = ui.make_persistent_id(("task_color", i));
+                        if color_response.clicked() {
+                            ui.memory_mut(|memory| memory.toggle_popup(color_popup_id));
+                        }
+                        egui::popup::popup_below_widget(
+                            ui,
+                            color_popup_id,
+                            &color_response,
+                            egui::PopupCloseBehavior::CloseOnClickOutside,
+                            |ui| {
+                                ui.horizontal(|ui| {
+                                    for color in LABEL_COLORS {
+                                        if color_swatch(ui, color, task.color == Some(color)).clicked() {
+                                            color_request = Some((i, Some(color), false));
+                                        }
+                                    }
+                                });
+                                let [r, g, b] = task.color.unwrap_or(LABEL_COLORS[0]);
+                                let mut custom = egui::Color32::from_rgb(r, g, b);
+                                // Перетаскивание в палитре меняет цвет каждый кадр и отменяется одним шагом
+                                if egui::color_picker::color_picker_color32(ui, &mut custom, egui::color_picker::Alpha::Opaque) {
+                                    color_request = Some((i, Some([custom.r(), custom.g(), custom.b()]), true));
+                                }
+                                if ui.button(t("No Color")).clicked() {
+                                    color_request = Some((i, None, false));
+                                }
+                            },
+                        );
+
+                        // Теги задачи; щелчок по тегу включает фильтр по нему
+                        for tag in &task.tags {
+                            let chip = egui::Button::new(egui::RichText::new(tag).small())
+                                .rounding(8.0)
+                                .small();
+                            if ui.add(chip).on_hover_text(t("Show tasks with this tag")).clicked() {
+                                tag_clicked = Some(tag.clone());
+                            }
+                        }
+
+                        // Добавление и удаление тегов
+                        let tags_response = ui.button("🏷").on_hover_text(t("Edit Tags"));
+                        let tags_popup_id = ui.make_persistent_id(("task_tags", i));
+                        if tags_response.clicked() {
+                            ui.memory_mut(|memory| memory.toggle_popup(tags_popup_id));
+                        }
+                        egui::popup::popup_below_widget(
+                            ui,
+                            tags_popup_id,
+                            &tags_response,
+                            egui::PopupCloseBehavior::CloseOnClickOutside,
+                            |ui| {
+                                ui.set_min_width(180.0);
+                                for tag in &task.tags {
+                                    ui.horizontal(|ui| {
+                                        ui.label(tag);
+                                        if ui.small_button("✖").on_hover_text(t("Remove Tag")).clicked() {
+                                            tag_removed = Some((i, tag.clone()));
+                                        }
+                                    });
+                                }
+                                ui.horizontal(|ui| {
+                                    let input = ui.add(egui::TextEdit::singleline(&mut self.tag_input)
+                                        .hint_text("@home")
+                                        .desired_width(110.0)
+                                    );
+                                    let submitted = input.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                                    let has_tag = !self.tag_input.trim().is_empty();
+                                    if ui.add_enabled(has_tag, egui::Button::new(t("Add"))).clicked() || (submitted && has_tag) {
+                                        tag_added = Some(i);
+                                    }
+                                });
+                            },
+                        );
+
+                        // Добавление шага задачи
+                        let step_response = ui.button("➕").on_hover_text(t("Add Subtask"));
+                        let step_popup_id = ui.make_persistent_id(("add_subtask", i));
+                        if step_response.clicked() {
+                            ui.memory_mut(|memory| memory.toggle_popup(step_popup_id));
+                        }
+                        egui::popup::popup_below_widget(
+                            ui,
+                            step_popup_id,
+                            &step_response,
+                            egui::PopupCloseBehavior::CloseOnClickOutside,
+                            |ui| {
+                                ui.horizontal(|ui| {
+                                    let input = ui.add(egui::TextEdit::singleline(&mut self.subtask_input)
+                                        .hint_text(t("Next step"))
+                                        .desired_width(160.0)
+                                    );
+                                    let submitted = input.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                                    let has_step = !self.subtask_input.trim().is_empty();
+                                    if ui.add_enabled(has_step, egui::Button::new(t("Add"))).clicked() || (submitted && has_step) {
+                                        subtask_added = Some(i);
+                                    }
+                                });
+                            },
+                        );
+
+                        // Выбор приоритета
+                        egui::ComboBox::from_id_salt(("priority", i))
+                            .selected_text(t(task.priority.label()))
+                            .width(80.0)
+                            .show_ui(ui, |ui| {
+                                for priority in Priority::ALL {
+                                    if ui.selectable_label(task.priority == priority, t(priority.label())).clicked()
+                                        && task.priority != priority
+                                    {
+                                        priority_request = Some((i, priority));
+                                    }
+                                }
+                            });
+
+                        // Срок выполнения: пустое поле убирает срок, ошибочная дата подсвечивается
+                        let mut due_text = task.due_date.clone().unwrap_or_default();
+                        let valid = due_text.trim().is_empty() || parse_due_date(&due_text).is_some();
+                        let mut due_editor = egui::TextEdit::singleline(&mut due_text)
+                            .hint_text("YYYY-MM-DD")
+                            .desired_width(80.0);
+                        if !valid {
+                            due_editor = due_editor.text_color(egui::Color32::RED);
+                        }
+                        let due_response = ui.add(due_editor).on_hover_text(t("Due date (YYYY-MM-DD)"));
+                        if due_response.changed() {
+                            due_edit = Some((i, due_text));
+                        }
+                        if due_response.lost_focus() {
+                            due_finished = true;
+                        }
+                        ui.menu_button("📅", |ui| {
+                            for preset in DuePreset::ALL {
+                                if ui.button(t(preset.label())).clicked() {
+                                    due_preset = Some((i, preset.date(today)));
+                                    ui.close_menu();
+                                }
+                            }
+
+                            // Перенос на любую дату: проверка та же, что у поля срока
+                            ui.separator();
+                            ui.label(t("Snooze until:"));
+                            let date = parse_due_date(&self.snooze_input);
+                            let invalid = date.is_none() && !self.snooze_input.trim().is_empty();
+                            let mut snooze_editor = egui::TextEdit::singleline(&mut self.snooze_input)
+                                .hint_text("YYYY-MM-DD")
+                                .desired_width(100.0);
+                            if invalid {
+                                snooze_editor = snooze_editor.text_color(egui::Color32::RED);
+                            }
+                            ui.add(snooze_editor);
+                            if date.is_some_and(|date| date < today) {
+                                ui.colored_label(egui::Color32::from_rgb(230, 150, 40), t("⚠ This date is in the past"));
+                            }
+                            if ui.add_enabled(date.is_some(), egui::Button::new(t("Snooze"))).clicked() {
+                                snooze_request = date.map(|date| (i, date));
+                                self.snooze_input.clear();
+                                ui.close_menu();
+                            }
+                        }).response.on_hover_text(t("Due date presets"));
+                        if overdue {
+                            ui.colored_label(egui::Color32::RED, t("⚠ overdue"));
+                        }
+
+                        // Время создания задачи
+                        ui.label(egui::RichText::new(task.created_label().unwrap_or_else(|| t("unknown").to_string()))
+                            .small()
+                            .color(egui::Color32::from_gray(120)));
+
+                        // Время выполнения задачи
+                        if task.completed {
+                            if let Some(completed) = task.completed_label() {
+                                ui.label(egui::RichText::new(format!("✔ {}", completed))
+                                    .small()
+                                    .color(egui::Color32::from_gray(120)));
+                            }
+                        }
+
+                        // Отметка о задачах, измененных сегодня
+                        if let Some(time) = task.edited_today(today, rollover_hour) {
+                            ui.label(egui::RichText::new("✎").small().weak())
+                                .on_hover_text(tf("Edited today at {}", &[&time]));
+                        }
+
+                        // Предупреждение о повторе при редактировании (сохранению не мешает)
+                        if duplicate {
+                            ui.colored_label(egui::Color32::from_rgb(230, 160, 40), t("⚠ duplicate"))
+                                .on_hover_text(t("Another task has the same description"));
+                        }
+
+                        // Подсказка о том, что такая задача уже выполнялась
+                        if let Some(day) = done_before {
+                            let hint = match day {
+                                Some(day) => tf("done before on {}", &[&day.format("%Y-%m-%d")]),
+                                None => t("done before").to_string(),
+                            };
+                            ui.label(egui::RichText::new(hint).small().weak())
+                                .on_hover_text(t("A completed task with the same description exists"));
+                        }
+
+                        // Закрепление задачи вверху списка
+                        let pin_hint = if task.pinned { t("Unpin Task") } else { t("Pin to Top") };
+                        if ui.selectable_label(task.pinned, "📌").on_hover_text(pin_hint).clicked() {
+                            pin_toggled = Some(i);
+                        }
+
+                        // Помидор для задачи и число уже потраченных
+                        if task.pomodoros > 0 {
+                            ui.label(egui::RichText::new(format!("🍅{}", task.pomodoros)).small())
+                                .on_hover_text(t("Finished pomodoros"));
+                        }
+                        if task.focus_secs >= 60 {
+                            ui.label(egui::RichText::new(tf("⏱{} min", &[&(task.focus_secs / 60)])).small())
+                                .on_hover_text(t("Time spent with the timer"));
+                        }
+                        let running = self.active_timer.is_some_and(|(index, _)| index == i);
+                        let (timer_label, timer_hint) = if running { ("⏹", t("Stop Pomodoro")) } else { ("▶", t("Start a 25-minute Pomodoro")) };
+                        if !task.completed && ui.button(timer_label).on_hover_text(timer_hint).clicked() {
+                            timer_request = Some(i);
+                        }
+
+                        // Перестановка с соседней видимой задачей
+                        if let Some(&(previous, next)) = neighbors.get(&i) {
+                            if ui.add_enabled(previous.is_some(), egui::Button::new("▲").small())
+                                .on_hover_text(t("Move Up"))
+                                .clicked()
+                            {
+                                reorder_request = previous.map(|previous| (i, previous));
+                            }
+                            if ui.add_enabled(next.is_some(), egui::Button::new("▼").small())
+                                .on_hover_text(t("Move Down"))
+                                .clicked()
+                            {
+                                reorder_request = next.map(|next| (i, next));
+                            }
+                        }
+
+                        // Кнопка показа только этой задачи
+                        if self.solo_task.is_none() && ui.button("👁").on_hover_text(t("Focus on This Task")).clicked() {
+                            solo_task = Some(i);
+                        }
+
+                        // Перенос задачи в другой список
+                        let move_response = ui.button("➡").on_hover_text(t("Move to Another List"));
+                        let move_popup_id = ui.make_persistent_id(("move_task", i));
+                        if move_response.clicked() {
+                            ui.memory_mut(|memory| memory.toggle_popup(move_popup_id));
+                        }
+                        egui::popup::popup_below_widget(
+                            ui,
+                            move_popup_id,
+                            &move_response,
+                            egui::PopupCloseBehavior::CloseOnClickOutside,
+                            |ui| {
+                                ui.set_min_width(200.0);
+                                for file in other_task_files(&task_dir, &self.save_path) {
+                                    if ui.button(&file).clicked() {
+                                        move_request = Some((i, file));
+                                    }
+                                }
+                                ui.horizontal(|ui| {
+                                    ui.add(egui::TextEdit::singleline(&mut self.move_destination)
+                                        .hint_text("other.json")
+                                        .desired_width(140.0)
+                                    );
+                                    let has_path = !self.move_destination.trim().is_empty();
+                                    if ui.add_enabled(has_path, egui::Button::new(t("Move"))).clicked() {
+                                        move_request = Some((i, self.move_destination.trim().to_string()));
+                                    }
+                                });
+                            },
+                        );
+
+                        // Копия задачи
+                        if ui.button("⧉").on_hover_text(t("Duplicate Task")).clicked() {
+                            duplicate_request = Some(i);
+                        }
+
+                        // Кнопка "Delete"
+                        if ui.button("🗑").on_hover_text(t("Delete Task")).clicked() {
+                            delete_request = Some(i);
+                        }
+                    });
+
+                    // Красный фон для просроченных задач, цвет выделения для отмеченных
+                    let fill = if overdue {
+                        Some(egui::Color32::from_rgba_unmultiplied(220, 40, 40, 60))
+                    } else if marked {
+                        Some(ui.visuals().selection.bg_fill.gamma_multiply(0.4))
+                    } else {
+                        None
+                    };
+                    if let Some(fill) = fill {
+                        ui.painter().set(background, egui::Shape::rect_filled(row.response.rect.expand(2.0), 4.0, fill));
+                    }
+
+                    // Цветная полоса слева от строки
+                    if let Some([r, g, b]) = task.color {
+                        let rect = row.response.rect;
+                        let strip = egui::Rect::from_min_max(rect.left_top() - egui::vec2(6.0, 0.0), rect.left_bottom() - egui::vec2(2.0, 0.0));
+                        ui.painter().rect_filled(strip, 1.0, egui::Color32::from_rgb(r, g, b));
+                    }
+
+                    // Заметки к задаче в раскрывающемся поле
+                    if self.notes_open.contains(&i) {
+                        ui.indent(("notes", i), |ui| {
+                            let query = &SearchQuery::parse(&self.search_query).text;
+                            let fuzzy = self.settings.fuzzy_search;
+                            let text_color = ui.visuals().override_text_color.unwrap_or(ui.visuals().widgets.inactive.text_color());
+                            let mut layouter = |ui: &egui::Ui, text: &str, wrap_width: f32| {
+                                let font_id = egui::TextStyle::Body.resolve(ui.style());
+                                let mut job = highlight_matches(text, &search_ranges(text, query, fuzzy), font_id, text_color);
+                                job.wrap.max_width = wrap_width;
+                                ui.fonts(|fonts| fonts.layout_job(job))
+                            };
+                            let mut notes = task.notes.clone();
+                            let mut editor = egui::TextEdit::multiline(&mut notes)
+                                .hint_text(t("Details, links, ideas..."))
+                                .desired_rows(4)
+                                .desired_width(400.0);
+                            if !query.is_empty() {
+                                editor = editor.layouter(&mut layouter);
+                            }
+                            let response = ui.add(editor);
+                            if response.changed() {
+                                notes_edit = Some((i, notes));
+                            }
+                            if response.lost_focus() {
+                                notes_finished = true;
+                            }
+                        });
+                    }
+
+                    // Шаги задачи с отступом под ней
+                    if !task.subtasks.is_empty() {
+                        ui.indent(("subtasks", i), |ui| {
+                            let mut steps_changed = false;
+                            for (n, subtask) in task.subtasks.iter_mut().enumerate() {
+                                ui.horizontal(|ui| {
+                                    let mut completed = subtask.completed;
+                                    if ui.checkbox(&mut completed, "").changed() {
+                                        subtask_toggled = Some((i, n, completed));
+                                    }
+                                    let mut editor = egui::TextEdit::singleline(&mut subtask.description)
+                                        .desired_width(260.0);
+                                    if subtask.completed {
+                                        editor = editor.text_color(egui::Color32::from_gray(120));
+                                    }
+                                    let response = ui.add(editor);
+                                    if response.gained_focus() {
+                                        subtask_focused = true;
+                                    }
+                                    steps_changed |= response.changed();
+                                    if response.lost_focus() {
+                                        subtask_edited = Some(i);
+                                    }
+                                    if ui.small_button("✖").on_hover_text(t("Remove Subtask")).clicked() {
+                                        subtask_removed = Some((i, n));
+                                    }
+                                });
+                            }
+                            if steps_changed {
+                                task.touch();
+                            }
+                        });
+                    }
+                };
+
+                // Сначала активные задачи, затем выполненные
+                for i in active_indices {
+                    show_row(ui, i);
+                }
+                let mut by_time_changed = false;
+                if !completed_indices.is_empty() {
+                    egui::CollapsingHeader::new(tf("Completed ({})", &[&completed_count]))
+                        .id_salt("completed_tasks")
+                        .show(ui, |ui| {
+                            by_time_changed = ui.checkbox(&mut completed_by_time, t("Most Recently Completed First")).changed();
+                            for i in completed_indices {
+                                show_row(ui, i);
+                            }
+                        });
+                }
+
+                // Порядок выполненных задач
+                if by_time_changed {
+                    self.settings.completed_by_time = completed_by_time;
+                    self.autosave();
+                }
+
+                // Отметка задачи для групповых действий
+                if let Some(task_index) = mark_toggled {
+                    if !self.marked.remove(&task_index) {
+                        self.marked.insert(task_index);
+                    }
+                }
+
+                // Изменение шагов задачи
+                if let Some(task_index) = subtask_added {
+                    let description = std::mem::take(&mut self.subtask_input).trim().to_string();
+                    self.record_undo();
+                    let task = &mut self.tasks_mut()[task_index];
+                    task.subtasks.push(Subtask { description, completed: false });
+                    task.touch();
+                    self.sync_subtasks(task_index);
+                    self.autosave();
+                }
+                if let Some((task_index, subtask_index, completed)) = subtask_toggled {
+                    self.record_undo();
+                    let task = &mut self.tasks_mut()[task_index];
+                    task.subtasks[subtask_index].completed = completed;
+                    task.touch();
+                    self.sync_subtasks(task_index);
+                    self.autosave();
+                }
+                if let Some((task_index, subtask_index)) = subtask_removed {
+                    self.record_undo();
+                    let task = &mut self.tasks_mut()[task_index];
+                    task.subtasks.remove(subtask_index);
+                    task.touch();
+                    self.sync_subtasks(task_index);
+                    self.autosave();
+                }
+                // Правка шага отменяется целиком, как правка описания
+                if let Some(task_index) = subtask_edited {
+                    self.finish_subtask_edit(task_index);
+                }
+                if subtask_focused {
+                    self.subtask_snapshot = Some(self.tasks().clone());
+                }
+
+                // Изменение тегов
+                if let Some(task_index) = tag_added {
+                    let input = std::mem::take(&mut self.tag_input);
+                    self.add_tags(task_index, &input);
+                }
+                if let Some((task_index, tag)) = tag_removed {
+                    self.remove_tag(task_index, &tag);
+                }
+                if tag_clicked.is_some() {
+                    self.tag_filter = tag_clicked;
+                    self.autosave();
+                }
+
+                if let Some((task_index, date)) = snooze_request {
+                    self.snooze_task(task_index, date);
+                }
+
+                if let Some((task_index, date)) = due_preset {
+                    self.record_undo();
+                    let task = &mut self.tasks_mut()[task_index];
+                    task.due_date = Some(date.format("%Y-%m-%d").to_string());
+                    task.notified = false;
+                    task.touch();
+                    self.autosave();
+                }
+
+                // Правки значка, приоритета, срока и цвета применяются после цикла,
+                // чтобы сохранить состояние для отмены
+                if let Some((task_index, icon)) = icon_request {
+                    self.record_undo();
+                    let task = &mut self.tasks_mut()[task_index];
+                    task.icon = icon;
+                    task.touch();
+                    self.autosave(); // Автосохранение
+                }
+                if let Some((task_index, priority)) = priority_request {
+                    self.record_undo();
+                    let task = &mut self.tasks_mut()[task_index];
+                    task.priority = priority;
+                    task.touch();
+                    self.autosave();
+                }
+                if let Some((task_index, due_text)) = due_edit {
+                    self.record_field_undo(task_index, TaskField::Due);
+                    let task = &mut self.tasks_mut()[task_index];
+                    task.due_date = (!due_text.trim().is_empty()).then(|| due_text.trim().to_string());
+                    task.notified = false;
+                    task.touch();
+                }
+                if let Some((task_index, color, dragged)) = color_request {
+                    if dragged {
+                        self.record_field_undo(task_index, TaskField::Color);
+                    } else {
+                        self.record_undo();
+                    }
+                    let task = &mut self.tasks_mut()[task_index];
+                    task.color = color;
+                    task.touch();
+                    self.autosave();
+                }
+                if due_finished {
+                    self.field_undo = None;
+                    self.autosave();
+                }
+
+                // Изменение статуса выполнения
+                if let Some((task_index, completed)) = toggled {
+                    self.record_undo();
+                    let task = &mut self.tasks_mut()[task_index];
+                    task.completed = completed;
+                    // Время выполнения ставится при отметке и сбрасывается при снятии
+                    task.completed_at = completed.then(|| Local::now().to_rfc3339());
+                    task.touch();
+                    let action = if task.completed { "complete" } else { "reopen" };
+                    let entry = format!("{}: {}", action, task.description);
+                    self.log_action(&entry);
+                    if !self.tasks()[task_index].completed {
+                        self.tasks_mut()[task_index].completion_note = None;
+                    } else if self.settings.note_on_complete {
+                        self.completion_prompt = Some((task_index, String::new()));
+                    }
+                    self.autosave(); // Автосохранение
+                }
+
+                if edited {
+                    self.description_edited = true;
+                    self.autosave(); // Правки сохраняются по ходу ввода с задержкой
+                }
+
+                if solo_task.is_some() {
+                    self.solo_task = solo_task;
+                }
+
+                // Завершение редактирования: сохраняем при потере фокуса
+                if let Some(task_index) = finished_edit {
+                    if self.selected_task == Some(task_index) {
+                        self.selected_task = None;
+                    }
+                    if cancelled_edit {
+                        // Возврат описания к состоянию до начала редактирования
+                        let original = self.edit_snapshot.as_ref().and_then(|snapshot| snapshot.get(task_index)).cloned();
+                        if let Some(original) = original.filter(|_| self.description_edited) {
+                            self.tasks_mut()[task_index] = original;
+                        }
+                        self.description_edited = false;
+                    } else if self.description_edited {
+                        let entry = format!("edit: {}", self.tasks()[task_index].description);
+                        self.log_action(&entry);
+                        self.description_edited = false;
+                        if let Some(snapshot) = self.edit_snapshot.take() {
+                            let mut lists = self.lists.clone();
+                            lists[self.current_list].tasks = snapshot;
+                            self.push_undo((lists, self.current_list, self.trash.clone()));
+                        }
+                    }
+                    self.edit_snapshot = None;
+                    self.autosave(); // Автосохранение
+                }
+
+                // Задача, поле которой получило фокус
+                if let Some(task_index) = edit_task {
+                    self.selected_task = Some(task_index);
+                    self.edit_snapshot = Some(self.tasks().clone());
+                }
+
+                // Перенос задачи в другой файл
+                if let Some((task_index, path)) = move_request {
+                    ui.memory_mut(|memory| memory.close_popup());
+                    // Относительные пути считаются от каталога файла задач
+                    let dest_path = self.task_dir().join(path);
+                    match self.move_task_to(task_index, &dest_path) {
+                        Ok(()) => self.move_destination.clear(),
+                        Err(error) => self.last_error = Some(error),
+                    }
+                }
+
+                // Запуск или остановка помидора; новый помидор заменяет запущенный,
+                // а время прежнего сохраняется так же, как при остановке
+                if let Some(task_index) = timer_request {
+                    let running = self.active_timer.is_some_and(|(index, _)| index == task_index);
+                    if self.active_timer.is_some() {
+                        self.stop_timer_and_save();
+                    }
+                    if !running {
+                        self.active_timer = Some((task_index, Instant::now()));
+                    }
+                }
+
+                // Раскрытие заметок
+                if let Some(task_index) = notes_toggled {
+                    if !self.notes_open.remove(&task_index) {
+                        self.notes_open.insert(task_index);
+                    }
+                }
+                if let Some((task_index, notes)) = notes_edit {
+                    self.record_field_undo(task_index, TaskField::Notes);
+                    let task = &mut self.tasks_mut()[task_index];
+                    task.notes = notes;
+                    task.touch();
+                }
+                if notes_finished {
+                    self.field_undo = None;
+                    self.autosave();
+                }
+
+                // Закрепление задачи
+                if let Some(task_index) = pin_toggled {
+                    self.record_undo();
+                    let task = &mut self.tasks_mut()[task_index];
+                    task.pinned = !task.pinned;
+                    task.touch();
+                    self.autosave();
+                }
+
+                // Перестановка меняет местами сами задачи, даже если между ними есть скрытые фильтром
+                if let Some((task_index, other)) = reorder_request {
+                    self.swap_tasks(task_index, other);
+                }
+
+                // Индекс берется из `self.tasks()`, поэтому копия встает за оригиналом и при фильтре
+                if let Some(task_index) = duplicate_request {
+                    self.duplicate_task(task_index);
+                }
+
+                // Удаление запрашивается через окно подтверждения
+                if delete_request.is_some() {
+                    self.pending_delete = delete_request;
+                }
+            });
+            self.scroll_offset = scroll.state.offset.y;
+
+            // Удаление или архивация выполненных задач, просмотр архива
+            ui.horizontal(|ui| {
+                if ui.button(t("Clear Completed")).on_hover_text(t("Move all completed tasks to the trash")).clicked() {
+                    self.confirm_clear_completed = true;
+                }
+                let archive_path = self.sibling_path(ARCHIVE_FILE);
+                if ui.button(t("Archive Completed"))
+                    .on_hover_text(tf("Move all completed tasks to {}", &[&archive_path.display()]))
+                    .clicked()
+                {
+                    match self.archive_completed() {
+                        Ok(_) => self.last_error = None,
+                        Err(error) => self.last_error = Some(error),
+                    }
+                }
+                if ui.button(t("View Archive")).clicked() {
+                    match read_archive(&archive_path) {
+                        Ok(archive) => self.archive_view = Some(archive),
+                        Err(error) => self.last_error = Some(error),
+                    }
+                }
+            });
+
+            // Корзина: удаленные задачи можно вернуть или удалить окончательно
+            egui::CollapsingHeader::new(tf("Trash ({})", &[&self.trash.len()]))
+                .id_salt("trash")
+                .show(ui, |ui| {
+                    if self.trash.is_empty() {
+                        ui.label(egui::RichText::new(t("The trash is empty")).weak());
+                    }
+                    let mut restore = None;
+                    let mut purge = None;
+                    for (n, trashed) in self.trash.iter().enumerate().rev() {
+                        ui.horizontal(|ui| {
+                            ui.label(&trashed.task.description);
+                            if let Some(time) = parse_timestamp(&trashed.deleted_at) {
+                                ui.label(egui::RichText::new(tf("deleted {}", &[&time.format("%Y-%m-%d %H:%M")]))
+                                    .small()
+                                    .color(egui::Color32::from_gray(120)));
+                            }
+                            if ui.button(t("Restore")).clicked() {
+                                restore = Some(n);
+                            }
+                            if ui.button(t("Delete Forever")).clicked() {
+                                purge = Some(n);
+                            }
+                        });
+                    }
+                    if let Some(index) = restore {
+                        self.restore_from_trash(index);
+                    }
+                    if let Some(index) = purge {
+                        self.purge_from_trash(index);
+                    }
+                    ui.label(egui::RichText::new(tf("Tasks are removed from the trash after {} days", &[&TRASH_RETENTION_DAYS]))
+                        .small()
+                        .weak());
+                });
+
+            // Импорт и экспорт; пустой путь означает файл по умолчанию рядом с файлом задач
+            egui::CollapsingHeader::new(t("Data")).show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(t("File:"));
+                    ui.add(egui::TextEdit::singleline(&mut self.data_path)
+                        .hint_text(t("default location"))
+                        .desired_width(220.0)
+                    );
+                });
+
+                // Импорт задач из текстового, Markdown- или CSV-файла
+                ui.horizontal(|ui| {
+                    let has_path = !self.data_path.trim().is_empty();
+                    let import_text = ui.add_enabled(has_path, egui::Button::new(t("Import Text")))
+                        .on_hover_text(t("Add each line of a text or Markdown file as a task"));
+                    let import_csv = ui.add_enabled(has_path, egui::Button::new(t("Import CSV")))
+                        .on_hover_text(t("Add the tasks from a CSV file"));
+                    if import_text.clicked() || import_csv.clicked() {
+                        let path = PathBuf::from(self.data_path.trim());
+                        let format = if import_csv.clicked() { ImportFormat::Csv } else { ImportFormat::Text };
+                        match self.preview_import(&path, format) {
+                            Ok(preview) => {
+                                self.import_preview = Some(preview);
+                                self.last_error = None;
+                            }
+                            Err(error) => self.last_error = Some(error),
+                        }
+                    }
+                });
+
+                // Экспорт задач
+                ui.horizontal(|ui| {
+                    let csv_path = self.data_path_or(CSV_FILE);
+                    if ui.button(t("Export CSV")).on_hover_text(tf("Save all tasks to {}", &[&csv_path.display()])).clicked() {
+                        self.last_error = fs::write(&csv_path, csv::to_csv(self.tasks()))
+                            .err()
+                            .map(|error| format!("Failed to export {}: {}", csv_path.display(), error));
+                    }
+
+                    // Экспорт видимых задач в PDF
+                    let pdf_path = self.data_path_or(PDF_FILE);
+                    if ui.button(t("Export PDF")).on_hover_text(tf("Save the visible tasks to {}", &[&pdf_path.display()])).clicked() {
+                        self.last_error = self.export_pdf(&pdf_path)
+                            .err()
+                            .map(|error| format!("Failed to export {}: {}", pdf_path.display(), error));
+                    }
+                });
+
+                // Восстановление из резервной копии, сделанной при запуске
+                ui.menu_button(t("Restore from Backup"), |ui| {
+                    let backups = backup_files(&self.save_path);
+                    if backups.is_empty() {
+                        ui.label(egui::RichText::new(t("No backups yet")).weak());
+                    }
+                    for (path, time) in backups {
+                        if ui.button(&time).on_hover_text(path.display().to_string()).clicked() {
+                            self.last_error = self.restore_backup(&path).err();
+                            ui.close_menu();
+                        }
+                    }
+                });
+
+                // Экспорт задач в Markdown-чеклист: в буфер обмена или в файл
+                ui.horizontal(|ui| {
+                    if ui.button(t("Copy as Markdown")).on_hover_text(t("Copy all tasks as a Markdown checklist")).clicked() {
+                        ui.output_mut(|output| output.copied_text = markdown::to_checklist(self.tasks()));
+                    }
+                    let markdown_path = self.data_path_or(MARKDOWN_FILE);
+                    if ui.button(t("Export Markdown")).on_hover_text(tf("Save all tasks to {}", &[&markdown_path.display()])).clicked() {
+                        self.last_error = fs::write(&markdown_path, markdown::to_checklist(self.tasks()))
+                            .err()
+                            .map(|error| format!("Failed to export {}: {}", markdown_path.display(), error));
+                    }
+                    let journal_path = self.data_path_or(JOURNAL_FILE);
+                    if ui.button(t("Export Journal"))
+                        .on_hover_text(tf("Save completed tasks grouped by day to {}", &[&journal_path.display()]))
+                        .clicked()
+                    {
+                        self.last_error = fs::write(&journal_path, self.export_journal())
+                            .err()
+                            .map(|error| format!("Failed to export {}: {}", journal_path.display(), error));
+                    }
+                    let stats_path = self.data_path_or(STATS_FILE);
+                    if ui.button(t("Export Stats"))
+                        .on_hover_text(tf("Save the list statistics to {}", &[&stats_path.display()]))
+                        .clicked()
+                    {
+                        self.last_error = fs::write(&stats_path, self.export_stats())
+                            .err()
+                            .map(|error| format!("Failed to export {}: {}", stats_path.display(), error));
+                    }
+                });
+            });
+        });
+
+        // Подтверждение сброса настроек
+        if self.confirm_reset {
+            if let Some(confirmed) = confirm_dialog(ctx, lang, t("Restore Defaults?"), t("All settings will be reset. Tasks are kept.")) {
+                if confirmed {
+                    self.restore_default_settings();
+                }
+                self.confirm_reset = false;
+            }
+        }
+
+        // Подтверждение удаления задачи
+        if let Some(index) = self.pending_delete {
+            let message = tf("Delete \"{}\"?", &[&self.tasks()[index].description]);
+            if let Some(confirmed) = confirm_dialog(ctx, lang, t("Delete Task?"), &message) {
+                self.pending_delete = None;
+                if confirmed {
+                    self.delete_task(index);
+                }
+            }
+        }
+
+        // Просмотр архива (только чтение), новые задачи сверху
+        if let Some(archive) = &self.archive_view {
+            let mut open = true;
+            egui::Window::new(tf("Archive ({})", &[&archive.len()]))
+                .open(&mut open)
+                .default_size([420.0, 360.0])
+                .show(ctx, |ui| {
+                    if archive.is_empty() {
+                        ui.label(egui::RichText::new(t("The archive is empty")).weak());
+                    }
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        for archived in archive.iter().rev() {
+                            ui.horizontal(|ui| {
+                                ui.label("✔");
+                                ui.label(&archived.task.description);
+                                ui.label(egui::RichText::new(&archived.list).small().weak());
+                                if let Some(completed) = archived.task.completed_label() {
+                                    ui.label(egui::RichText::new(tf("completed {}", &[&completed]))
+                                        .small()
+                                        .color(egui::Color32::from_gray(120)));
+                                }
+                            });
+                            if let Some(note) = &archived.task.completion_note {
+                                ui.indent(("archived_note", &archived.archived_at, &archived.task.created_at), |ui| {
+                                    ui.label(egui::RichText::new(note).italics().weak());
+                                });
+                            }
+                        }
+                    });
+                });
+            if !open {
+                self.archive_view = None;
+            }
+        }
+
+        // Подтверждение удаления всех выполненных задач
+        if self.confirm_clear_completed {
+            let message = tf("Remove {} completed task(s)?", &[&self.completed_tasks()]);
+            if let Some(confirmed) = confirm_dialog(ctx, lang, t("Clear Completed?"), &message) {
+                if confirmed {
+                    self.clear_completed();
+                }
+                self.confirm_clear_completed = false;
+            }
+        }
+
+        // Подтверждение удаления открытого списка
+        if self.confirm_delete_list {
+            let list = &self.lists[self.current_list];
+            let message = tf("Delete the list \"{}\"? Its {} task(s) will be moved to the trash.", &[&list.name, &list.tasks.len()]);
+            if let Some(confirmed) = confirm_dialog(ctx, lang, t("Delete List?"), &message) {
+                if confirmed {
+                    self.delete_list();
+                }
+                self.confirm_delete_list = false;
+            }
+        }
+
+        // Подтверждение удаления отмеченных задач
+        if self.confirm_delete_marked {
+            let message = tf("Delete {} selected task(s)?", &[&self.marked.len()]);
+            if let Some(confirmed) = confirm_dialog(ctx, lang, t("Delete Selected?"), &message) {
+                if confirmed {
+                    self.delete_marked();
+                }
+                self.confirm_delete_marked = false;
+            }
+        }
+
+        // Предпросмотр импорта: задачи добавляются только после подтверждения
+        if let Some(preview) = self.import_preview.take() {
+            let mut answer = None;
+            egui::Window::new(t("Import Preview"))
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                .show(ctx, |ui| {
+                    ui.label(tf("{} tasks in {}", &[&preview.tasks.len(), &preview.path.display()]));
+                    if preview.duplicates > 0 {
+                        ui.colored_label(egui::Color32::from_rgb(230, 150, 40), tf("⚠ {} of them are already in the list", &[&preview.duplicates]));
+                    }
+                    if !preview.errors.is_empty() {
+                        ui.colored_label(egui::Color32::RED, tf("{} malformed rows will be skipped", &[&preview.errors.len()]));
+                    }
+                    for task in preview.sample() {
+                        let mark = if task.completed { "☑" } else { "☐" };
+                        ui.label(format!("{} {}", mark, task.description.lines().next().unwrap_or_default()));
+                    }
+                    if preview.tasks.len() > ImportPreview::SAMPLE_ROWS {
+                        ui.label(egui::RichText::new(tf("...and {} more", &[&(preview.tasks.len() - ImportPreview::SAMPLE_ROWS)])).weak());
+                    }
+                    ui.horizontal(|ui| {
+                        if ui.add_enabled(!preview.tasks.is_empty(), egui::Button::new(t("Import"))).clicked() {
+                            answer = Some(true);
+                        }
+                        if ui.button(t("Cancel")).clicked() {
+                            answer = Some(false);
+                        }
+                    });
+                });
+            match answer {
+                Some(true) => match self.apply_import(preview) {
+                    Ok(_) => {
+                        self.data_path.clear();
+                        self.last_error = None;
+                    }
+                    Err(error) => self.last_error = Some(error),
+                },
+                Some(false) => {}
+                None => self.import_preview = Some(preview),
+            }
+        }
+
+        // Выход при запущенном помидоре
+        if self.confirm_quit {
+            if let Some((index, _)) = self.active_timer {
+                egui::Window::new(t("Quit"))
+                    .collapsible(false)
+                    .resizable(false)
+                    .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                    .show(ctx, |ui| {
+                        ui.label(tf("A pomodoro is running for \"{}\". Stop it and quit?", &[&self.tasks()[index].description]));
+                        ui.horizontal(|ui| {
+                            if ui.button(t("Stop & Save")).clicked() {
+                                self.stop_timer_and_save();
+                                self.confirm_quit = false;
+                                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                            }
+                            if ui.button(t("Cancel")).clicked() {
+                                self.confirm_quit = false;
+                            }
+                        });
+                    });
+            } else {
+                self.confirm_quit = false; // Помидор закончился, пока диалог был открыт
+            }
+        }
+
+        // Заметка о выполнении задачи (можно пропустить)
+        if let Some((task_index, mut note)) = self.completion_prompt.take() {
+            let mut done = false;
+            egui::Window::new(t("Completion Note"))
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                .show(ctx, |ui| {
+                    ui.label(tf("How did \"{}\" go?", &[&self.tasks()[task_index].description]));
+                    ui.add(egui::TextEdit::multiline(&mut note)
+                        .desired_rows(3)
+                        .desired_width(300.0)
+                    );
+                    ui.horizontal(|ui| {
+                        if ui.button(t("Save Note")).clicked() {
+                            self.save_completion_note(task_index, &note);
+                            done = true;
+                        }
+                        if ui.button(t("Skip")).clicked() {
+                            done = true;
+                        }
+                    });
+                });
+            if !done {
+                self.completion_prompt = Some((task_index, note));
+            }
+        }
+
+        self.tick_pomodoro(ctx);
+        self.notify_overdue();
+        self.update_recovery_snapshot();
+        self.flush_if_due();
+
+        // Всплывающее подтверждение внизу окна
+        if let Some((message, shown)) = &self.toast {
+            if shown.elapsed() < TOAST_DURATION {
+                egui::Area::new(egui::Id::new("toast"))
+                    .anchor(egui::Align2::CENTER_BOTTOM, egui::vec2(0.0, -24.0))
+                    .interactable(false)
+                    .show(ctx, |ui| {
+                        egui::Frame::popup(ui.style()).show(ui, |ui| ui.label(message.as_str()));
+                    });
+            } else {
+                self.toast = None;
+            }
+        }
+
+        // Перерисовка интерфейса для обновления времени
+        ctx.request_repaint();
+    }
+
+    // Финальное сохранение при закрытии окна. Запись безусловная: ввод новой задачи и
+    // черновик не помечают состояние измененным, но тоже хранятся в файле задач.
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.flush_save();
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let strict = args.iter().any(|arg| arg == "--strict");
+    let save_path = resolve_save_path(&args);
+    let recovery_path = with_suffix(&save_path, RECOVERY_SUFFIX);
+
+    let mut app = TodoApp::load_tasks(save_path, strict); // Загрузка задач при старте приложения
+    app.recovery_available = recovery_path.exists();
+    install_signal_handlers();
+    install_panic_hook(recovery_path);
+
+    // Окно открывается с сохраненными размером и положением; при первом запуске — по умолчанию
+    let mut viewport = egui::ViewportBuilder::default();
+    if let Some(size) = app.window_size {
+        viewport = viewport.with_inner_size(size);
+    }
+    if let Some(position) = app.window_position {
+        viewport = viewport.with_position(position);
+    }
+    let options = eframe::NativeOptions { viewport, ..Default::default() };
+
+    eframe::run_native(
+        "To-Do List",
+        options,
+        Box::new(|_cc| Ok(Box::new(app))),
+    )
+    .unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    // Приложение с одним списком из заданных задач
+    fn app_with(tasks: Vec<Task>) -> TodoApp {
+        let mut app = TodoApp::default();
+        app.migrate();
+        *app.tasks_mut() = tasks;
+        app
+    }
+
+    fn completed_on(description: &str, day: u32) -> Task {
+        let mut task = Task::new(description.to_string());
+        task.completed = true;
+        task.completed_at = Some(Local.with_ymd_and_hms(2024, 3, day, 12, 0, 0).unwrap().to_rfc3339());
+        task
+    }
+
+    #[test]
+    fn done_before_reports_latest_completion() {
+        let mut legacy = Task::new("Water plants".to_string());
+        legacy.completed = true;
+        let app = app_with(vec![
+            completed_on("Pay  rent", 1),
+            completed_on("pay rent", 4),
+            Task::new("PAY RENT ".to_string()),
+            Task::new("Buy milk".to_string()),
+            legacy,
+            Task::new("water plants".to_string()),
+        ]);
+        let history = app.completion_history();
+        assert_eq!(app.done_before(2, &history), Some(NaiveDate::from_ymd_opt(2024, 3, 4)));
+        assert_eq!(app.done_before(3, &history), None);
+        assert_eq!(app.done_before(5, &history), Some(None));
+        // Сама выполненная задача подсказку не получает
+        assert_eq!(app.done_before(0, &history), None);
+    }
+
+    #[test]
+    fn completed_within_respects_week_boundaries() {
+        let day = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let wednesday = day(2024, 3, 13);
+        assert!(CompletedWithin::ThisWeek.contains(day(2024, 3, 11), wednesday)); // Понедельник
+        assert!(CompletedWithin::ThisWeek.contains(day(2024, 3, 17), wednesday)); // Воскресенье
+        assert!(!CompletedWithin::ThisWeek.contains(day(2024, 3, 10), wednesday));
+        assert!(!CompletedWithin::ThisWeek.contains(day(2024, 3, 18), wednesday));
+        // Неделя на стыке лет относится к обоим годам, месяц — нет
+        let new_year = day(2025, 1, 1);
+        assert!(CompletedWithin::ThisWeek.contains(day(2024, 12, 30), new_year));
+        assert!(!CompletedWithin::ThisMonth.contains(day(2024, 12, 30), new_year));
+        assert!(!CompletedWithin::ThisWeek.contains(day(2024, 12, 29), new_year));
+        // Тот же номер недели в другом году — другая неделя
+        assert!(!CompletedWithin::ThisWeek.contains(day(2023, 3, 15), wednesday));
+        assert!(CompletedWithin::ThisMonth.contains(day(2024, 3, 1), wednesday));
+        assert!(CompletedWithin::Today.contains(wednesday, wednesday));
+        assert!(!CompletedWithin::Today.contains(day(2024, 3, 12), wednesday));
+    }
+
+    #[test]
+    fn completed_within_needs_completion_time() {
+        let today = NaiveDate::from_ymd_opt(2024, 3, 13).unwrap();
+        let mut legacy = Task::new("Old".to_string());
+        legacy.completed = true;
+        assert!(CompletedWithin::AnyTime.matches(&legacy, today, 0));
+        assert!(!CompletedWithin::ThisMonth.matches(&legacy, today, 0));
+        assert!(CompletedWithin::ThisWeek.matches(&completed_on("Recent", 11), today, 0));
+    }
+
+    #[test]
+    fn task_icon_round_trips_through_json() {
+        let mut with_icon = Task::new("Fix bug".to_string());
+        with_icon.icon = Some("🐛".to_string());
+        let tasks = vec![with_icon, Task::new("Plain".to_string())];
+        let json = serde_json::to_string(&tasks).unwrap();
+        let loaded: Vec<Task> = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded[0].icon.as_deref(), Some("🐛"));
+        assert_eq!(loaded[1].icon, None);
+        // В старых файлах поля нет
+        let legacy: Task = serde_json::from_str(r#"{"description": "Old", "completed": false}"#).unwrap();
+        assert_eq!(legacy.icon, None);
+    }
+
+    #[test]
+    fn completed_tasks_sort_by_completion_time() {
+        let mut legacy = Task::new("Legacy".to_string());
+        legacy.completed = true;
+        legacy.created_at = Local.with_ymd_and_hms(2024, 3, 3, 8, 0, 0).unwrap().to_rfc3339();
+        let undated = Task { completed: true, ..Default::default() };
+        let app = app_with(vec![completed_on("First", 2), undated, completed_on("Last", 5), legacy]);
+        let mut indices = vec![0, 1, 2, 3];
+        app.sort_by_completion(&mut indices);
+        // Без времени выполнения используется время создания, задача без времени — в конце
+        assert_eq!(indices, vec![2, 3, 0, 1]);
+    }
+
+    #[test]
+    fn shift_index_follows_removal() {
+        assert_eq!(shift_index(Some(1), 3), Some(1));
+        assert_eq!(shift_index(Some(3), 3), None);
+        assert_eq!(shift_index(Some(5), 3), Some(4));
+        assert_eq!(shift_index(None, 3), None);
+    }
+
+    #[test]
+    fn deleting_filtered_task_keeps_selection() {
+        let mut app = app_with(["buy milk", "call mom", "buy bread", "walk dog"].map(|d| Task::new(d.to_string())).to_vec());
+        app.search_query = "buy".to_string();
+        app.selected_task = Some(3);
+        app.marked.insert(2);
+        assert_eq!(app.visible_task_indices(), vec![0, 2]);
+
+        // Удаляется видимая задача, а выбрана задача, скрытая поиском
+        app.delete_task(0);
+        assert_eq!(app.selected_task, Some(2));
+        assert_eq!(app.tasks()[2].description, "walk dog");
+        assert_eq!(app.marked, HashSet::from([1]));
+        assert_eq!(app.visible_task_indices(), vec![1]);
+
+        // Удаление выбранной задачи снимает выбор
+        app.delete_task(2);
+        assert_eq!(app.selected_task, None);
+    }
+
+    #[test]
+    fn due_presets_from_fixed_day() {
+        let day = |d| NaiveDate::from_ymd_opt(2024, 3, d).unwrap();
+        // 13 марта 2024 года — среда
+        assert_eq!(DuePreset::Today.date(day(13)), day(13));
+        assert_eq!(DuePreset::Tomorrow.date(day(13)), day(14));
+        assert_eq!(DuePreset::NextWeek.date(day(13)), day(18));
+        assert_eq!(DuePreset::ThisWeekend.date(day(13)), day(16));
+        // Завтра через границу месяца
+        assert_eq!(DuePreset::Tomorrow.date(day(31)), NaiveDate::from_ymd_opt(2024, 4, 1).unwrap());
+    }
+
+    #[test]
+    fn due_presets_around_weekend() {
+        let day = |d| NaiveDate::from_ymd_opt(2024, 3, d).unwrap();
+        // Пятница, суббота, воскресенье и понедельник
+        assert_eq!(DuePreset::ThisWeekend.date(day(15)), day(16));
+        assert_eq!(DuePreset::ThisWeekend.date(day(16)), day(16));
+        assert_eq!(DuePreset::ThisWeekend.date(day(17)), day(17));
+        assert_eq!(DuePreset::ThisWeekend.date(day(18)), day(23));
+        assert_eq!(DuePreset::NextWeek.date(day(17)), day(18));
+        assert_eq!(DuePreset::NextWeek.date(day(18)), day(25));
+    }
+
+    #[test]
+    fn completion_note_lands_on_task() {
+        let mut app = app_with(vec![completed_on("Pay rent", 4), completed_on("Buy milk", 5)]);
+        app.save_completion_note(1, "  Paid cash\n");
+        assert_eq!(app.tasks()[1].completion_note.as_deref(), Some("Paid cash"));
+        assert!(app.tasks()[0].completion_note.is_none());
+        assert!(app.dirty);
+        // Пустая заметка не затирает сохраненную
+        app.save_completion_note(1, "   ");
+        assert_eq!(app.tasks()[1].completion_note.as_deref(), Some("Paid cash"));
+    }
+
+    #[test]
+    fn fuzzy_highlight_follows_char_boundaries() {
+        // "ё" и "ж" занимают по два байта, поэтому номера символов и байтов расходятся
+        assert_eq!(fuzzy::match_indices("Ёжик в тумане", "ёжк"), Some(vec![0, 1, 3]));
+        assert_eq!(fuzzy_ranges("Ёжик в тумане", "ёжк"), vec![0..4, 6..8]);
+        assert_eq!(fuzzy_ranges("Buy milk", "mlk"), vec![4..5, 6..8]);
+        // Слова запроса подсвечиваются в любом порядке
+        assert_eq!(fuzzy_ranges("Pay rent", "rent pay"), vec![0..3, 4..8]);
+        assert!(fuzzy_ranges("Buy milk", "xyz").is_empty());
+        // Буква, дающая при переводе в нижний регистр две, подсвечивается целиком
+        assert_eq!(fuzzy_ranges("İstanbul", "istan"), vec![0..6]);
+    }
+
+    #[test]
+    fn bulk_priority_is_one_undo_step() {
+        let mut app = app_with(vec![
+            Task::new("Pay rent".to_string()),
+            Task::new("Buy milk".to_string()),
+            Task::new("Call mom".to_string()),
+        ]);
+        app.marked = HashSet::from([0, 2]);
+        app.set_marked_priority(Priority::High);
+        let priorities: Vec<Priority> = app.tasks().iter().map(|task| task.priority).collect();
+        assert!(priorities == [Priority::High, Priority::Medium, Priority::High]);
+        assert!(app.marked.is_empty());
+        assert!(app.dirty);
+        app.undo();
+        assert!(app.tasks().iter().all(|task| task.priority == Priority::Medium));
+    }
+
+    #[test]
+    fn move_task_between_files() {
+        let dir = std::env::temp_dir().join(format!("yuko-todo-move-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let dest_path = dir.join("work.json");
+        let mut work = app_with(vec![Task::new("Write report".to_string())]);
+        work.save_path = dest_path.clone();
+        work.flush_save();
+
+        let mut app = app_with(vec![Task::new("Pay rent".to_string()), Task::new("Buy milk".to_string())]);
+        app.save_path = dir.join("home.json");
+        app.record_undo();
+        app.move_task_to(0, &dest_path).unwrap();
+        // Отмена не должна вернуть задачу, которая уже лежит в другом файле
+        assert!(app.undo_stack.is_empty());
+        assert!(app.move_task_to(0, &app.save_path.clone()).is_err());
+
+        let home = read_task_file(&app.save_path).unwrap();
+        let mut work = read_task_file(&dest_path).unwrap();
+        work.migrate();
+        let descriptions = |app: &TodoApp| app.tasks().iter().map(|task| task.description.clone()).collect::<Vec<_>>();
+        assert_eq!(descriptions(&app), ["Buy milk"]);
+        assert_eq!(descriptions(&home), ["Buy milk"]);
+        assert_eq!(descriptions(&work), ["Write report", "Pay rent"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn night_completion_counts_for_previous_day() {
+        let mut task = Task::new("Late deploy".to_string());
+        task.completed = true;
+        task.completed_at = Some(Local.with_ymd_and_hms(2024, 3, 12, 1, 0, 0).unwrap().to_rfc3339());
+        let day = |d| NaiveDate::from_ymd_opt(2024, 3, d).unwrap();
+        assert_eq!(task.completed_day(0), Some(day(12)));
+        assert_eq!(task.completed_day(3), Some(day(11)));
+        assert!(CompletedWithin::Today.matches(&task, day(11), 3));
+        assert!(!CompletedWithin::Today.matches(&task, day(12), 3));
+        // Ровно в час смены дня начинается новый день
+        let at_rollover = Local.with_ymd_and_hms(2024, 3, 12, 3, 0, 0).unwrap();
+        assert_eq!(effective_date(at_rollover, 3), day(12));
+        assert_eq!(effective_date(at_rollover - chrono::Duration::seconds(1), 3), day(11));
+    }
+
+    #[test]
+    fn stopping_timer_on_quit_saves_elapsed_time() {
+        let dir = std::env::temp_dir().join(format!("yuko-todo-quit-{}", std::process::id()));
+        let mut app = app_with(vec![Task::new("Write report".to_string())]);
+        app.save_path = dir.join("tasks.json");
+        app.active_timer = Some((0, Instant::now() - Duration::from_secs(10 * 60)));
+        app.stop_timer_and_save();
+        assert!(app.active_timer.is_none());
+        // Прерванный помидор не засчитывается, но отработанное время сохраняется
+        assert_eq!(app.tasks()[0].pomodoros, 0);
+        assert_eq!(app.tasks()[0].focus_secs / 60, 10);
+        let saved = read_task_file(&app.save_path).unwrap();
+        assert_eq!(saved.tasks()[0].focus_secs / 60, 10);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn search_tokens_are_parsed_out_of_the_query() {
+        let search = SearchQuery::parse("rent Priority:HIGH tag:#Home due:2024-03-15 tag:bills");
+        assert_eq!(search.text, "rent");
+        assert!(search.priority == Some(Priority::High));
+        assert_eq!(search.tags, ["home", "bills"]);
+        assert_eq!(search.due, Some(DueToken::On(NaiveDate::from_ymd_opt(2024, 3, 15).unwrap())));
+        // Нераспознанное значение остается текстом, запрос без условий не меняется
+        assert_eq!(SearchQuery::parse("priority:urgent call").text, "priority:urgent call");
+        assert_eq!(SearchQuery::parse("  buy  milk").text, "  buy  milk");
+        assert_eq!(SearchQuery::parse("due:overdue").due, Some(DueToken::Overdue));
+        assert_eq!(SearchQuery::parse("due:none").due, Some(DueToken::None));
+    }
+
+    #[test]
+    fn search_tokens_filter_tasks() {
+        let task = |description: &str, priority, tags: &[&str], due: Option<&str>| Task {
+            priority,
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            due_date: due.map(str::to_string),
+            ..Task::new(description.to_string())
+        };
+        let today = Local::now().date_naive();
+        let tomorrow = (today + chrono::Duration::days(1)).format("%Y-%m-%d").to_string();
+        let yesterday = (today - chrono::Duration::days(1)).format("%Y-%m-%d").to_string();
+        let mut app = app_with(vec![
+            task("Pay rent", Priority::High, &["#home", "#bills"], Some(&yesterday)),
+            task("Buy milk", Priority::Low, &["#home"], Some(&tomorrow)),
+            task("Write report", Priority::High, &["#work"], None),
+            task("Pay taxes", Priority::Medium, &["#bills"], Some(&tomorrow)),
+        ]);
+        let mut visible = |query: &str| {
+            app.search_query = query.to_string();
+            app.filtered_tasks().iter().map(|(i, _)| *i).collect::<Vec<_>>()
+        };
+        assert_eq!(visible("priority:high"), [0, 2]);
+        assert_eq!(visible("tag:home"), [0, 1]);
+        assert_eq!(visible("tag:HOME tag:#bills"), [0]);
+        assert_eq!(visible("due:overdue"), [0]);
+        assert_eq!(visible("due:tomorrow"), [1, 3]);
+        assert_eq!(visible("due:none"), [2]);
+        assert_eq!(visible(&format!("due:{}", tomorrow)), [1, 3]);
+        // Условия сочетаются друг с другом и с текстом
+        assert_eq!(visible("pay tag:bills"), [0, 3]);
+        assert_eq!(visible("pay tag:bills due:tomorrow"), [3]);
+        assert_eq!(visible("priority:high tag:work report"), [2]);
+        assert!(visible("priority:low tag:work").is_empty());
+    }
+
+    #[test]
+    fn old_tasks_get_update_time_from_creation() {
+        let created = Local.with_ymd_and_hms(2024, 3, 1, 9, 0, 0).unwrap().to_rfc3339();
+        let mut app = TodoApp {
+            lists: vec![TaskList {
+                name: DEFAULT_LIST_NAME.to_string(),
+                tasks: vec![
+                    Task { created_at: created.clone(), ..Task::new("Old task".to_string()) },
+                    Task { created_at: String::new(), ..Task::new("Unknown age".to_string()) },
+                ],
+                ..TaskList::default()
+            }],
+            ..TodoApp::default()
+        };
+        app.migrate();
+        assert_eq!(app.tasks()[0].updated_at.as_deref(), Some(created.as_str()));
+        assert!(app.tasks()[1].updated_at.is_none());
+        // Время создания не выдается за правку
+        assert!(app.tasks()[0].edited_today(NaiveDate::from_ymd_opt(2024, 3, 1).unwrap(), 0).is_none());
+    }
+
+    #[test]
+    fn editing_bumps_update_time() {
+        let before = Local.with_ymd_and_hms(2024, 3, 1, 9, 0, 0).unwrap().to_rfc3339();
+        let mut app = app_with(vec![Task { updated_at: Some(before.clone()), ..Task::new("Pay rent".to_string()) }]);
+        assert!(app.add_tags(0, "#home"));
+        let updated = app.tasks()[0].updated_at.as_deref().and_then(parse_timestamp).unwrap();
+        assert!(updated > parse_timestamp(&before).unwrap());
+        assert_eq!(app.tasks()[0].edited_today(Local::now().date_naive(), 0), Some(updated.format("%H:%M").to_string()));
+    }
+
+    #[test]
+    fn import_preview_does_not_change_the_list() {
+        let dir = std::env::temp_dir().join(format!("yuko-todo-import-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let text_path = dir.join("tasks.md");
+        let lines: Vec<String> = (1..=7).map(|n| format!("- [ ] Step {}", n)).collect();
+        fs::write(&text_path, format!("- [x] Pay rent\n{}\n- [ ] step 1\n", lines.join("\n"))).unwrap();
+        let mut app = app_with(vec![Task::new("pay  RENT".to_string())]);
+
+        let preview = app.preview_import(&text_path, ImportFormat::Text).unwrap();
+        assert_eq!(preview.tasks.len(), 9);
+        assert_eq!(preview.duplicates, 2); // "Pay rent" уже в списке, "step 1" повторяется в файле
+        assert!(preview.errors.is_empty());
+        let sample: Vec<&str> = preview.sample().iter().map(|task| task.description.as_str()).collect();
+        assert_eq!(sample, ["Pay rent", "Step 1", "Step 2", "Step 3", "Step 4"]);
+        assert!(preview.sample()[0].completed);
+        assert_eq!(app.tasks().len(), 1);
+
+        assert_eq!(app.apply_import(preview), Ok(9));
+        assert_eq!(app.tasks().len(), 10);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn import_preview_lists_malformed_csv_rows() {
+        let dir = std::env::temp_dir().join(format!("yuko-todo-import-csv-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let csv_path = dir.join("tasks.csv");
+        let csv = csv::to_csv(&[Task::new("Buy milk".to_string())]);
+        fs::write(&csv_path, format!("{}\"Broken row\",true\r\n", csv)).unwrap();
+        let mut app = app_with(Vec::new());
+
+        let preview = app.preview_import(&csv_path, ImportFormat::Csv).unwrap();
+        assert_eq!(preview.tasks.len(), 1);
+        assert_eq!(preview.duplicates, 0);
+        assert_eq!(preview.errors.len(), 1);
+        assert!(app.tasks().is_empty());
+        assert!(app.apply_import(preview).is_err());
+        assert_eq!(app.tasks()[0].description, "Buy milk");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn snoozing_sets_the_chosen_date() {
+        let mut app = app_with(vec![Task { due_date: Some("2024-03-01".to_string()), notified: true, ..Task::new("Pay rent".to_string()) }]);
+        app.snooze_task(0, NaiveDate::from_ymd_opt(2024, 4, 2).unwrap());
+        assert_eq!(app.tasks()[0].due_date.as_deref(), Some("2024-04-02"));
+        assert!(!app.tasks()[0].notified);
+        assert_eq!(app.toast.as_ref().map(|(message, _)| message.as_str()), Some("Snoozed until 2024-04-02"));
+        app.undo();
+        assert_eq!(app.tasks()[0].due_date.as_deref(), Some("2024-03-01"));
+    }
+
+    #[test]
+    fn search_is_remembered_per_list() {
+        let mut app = app_with(vec![Task::new("Buy milk".to_string())]);
+        app.lists.push(TaskList::new("Work".to_string()));
+        app.search_query = "milk".to_string();
+        app.switch_list(1);
+        assert_eq!(app.search_query, "");
+        app.search_query = "report".to_string();
+        app.switch_list(0);
+        assert_eq!(app.search_query, "milk");
+        app.switch_list(1);
+        assert_eq!(app.search_query, "report");
+        // Запрос удаленного списка пропадает, открытый список возвращает свой
+        app.delete_list();
+        assert_eq!(app.search_query, "milk");
+    }
+
+    #[test]
+    fn global_search_survives_list_switch() {
+        let mut app = app_with(Vec::new());
+        app.settings.global_search = true;
+        app.lists.push(TaskList::new("Work".to_string()));
+        app.search_query = "milk".to_string();
+        app.switch_list(1);
+        assert_eq!(app.search_query, "milk");
+        app.switch_list(0);
+        assert_eq!(app.search_query, "milk");
+    }
+
+    #[test]
+    fn stale_tasks_are_old_and_open() {
+        let now = Local.with_ymd_and_hms(2024, 3, 21, 12, 0, 0).unwrap();
+        let created = |day, hour| Task {
+            created_at: Local.with_ymd_and_hms(2024, 3, day, hour, 0, 0).unwrap().to_rfc3339(),
+            ..Task::new("Task".to_string())
+        };
+        assert!(created(1, 12).is_stale(now, 14));
+        assert!(created(7, 11).is_stale(now, 14));
+        // Ровно 14 дней — еще не залежалась
+        assert!(!created(7, 12).is_stale(now, 14));
+        assert!(!created(20, 12).is_stale(now, 14));
+        assert!(!Task { completed: true, ..created(1, 12) }.is_stale(now, 14));
+        assert!(!Task { created_at: String::new(), ..created(1, 12) }.is_stale(now, 14));
+
+        let app = app_with(vec![
+            Task { created_at: (Local::now() - chrono::Duration::days(30)).to_rfc3339(), ..Task::new("Old".to_string()) },
+            Task::new("New".to_string()),
+        ]);
+        assert_eq!(app.stale_tasks(14), vec![0]);
+    }
+
+    #[test]
+    fn field_edits_are_undoable() {
+        let mut app = app_with(vec![Task::new("Pay rent".to_string())]);
+        // Ввод заметки по буквам отменяется одним шагом
+        for notes in ["C", "Ca", "Cash"] {
+            app.record_field_undo(0, TaskField::Notes);
+            app.tasks_mut()[0].notes = notes.to_string();
+        }
+        assert_eq!(app.undo_stack.len(), 1);
+        app.tasks_mut()[0].completed = true;
+        app.save_completion_note(0, "Paid by card");
+        assert_eq!(app.undo_stack.len(), 2);
+
+        app.undo();
+        assert_eq!(app.tasks()[0].completion_note, None);
+        app.undo();
+        assert_eq!(app.tasks()[0].notes, "");
+    }
+
+    #[test]
+    fn archive_is_not_a_move_destination() {
+        let dir = std::env::temp_dir().join(format!("yuko-todo-other-files-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for name in ["tasks.json", "work.json", ARCHIVE_FILE, "notes.txt"] {
+            fs::write(dir.join(name), "{}").unwrap();
+        }
+        assert_eq!(other_task_files(&dir, &dir.join("tasks.json")), ["work.json"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn launch_filter_falls_back_to_all() {
+        let mut due_later = Task::new("Renew passport".to_string());
+        due_later.due_date = Some("2999-01-01".to_string());
+        let mut app = app_with(vec![Task::new("Buy milk".to_string()), due_later]);
+        app.settings.focus_today = true;
+        app.apply_launch_filter();
+        assert!(app.settings.status_filter == StatusFilter::All);
+
+        // Просроченная задача попадает в фильтр "Сегодня"
+        app.tasks_mut()[0].due_date = Some("2000-01-01".to_string());
+        app.apply_launch_filter();
+        assert!(app.settings.status_filter == StatusFilter::Today);
+        assert_eq!(app.visible_task_indices(), [0]);
+    }
+
+    #[test]
+    fn subtask_edit_is_one_undo_step() {
+        let mut task = Task::new("Plan trip".to_string());
+        task.subtasks.push(Subtask { description: "Book".to_string(), completed: false });
+        let mut app = app_with(vec![task]);
+        app.subtask_snapshot = Some(app.tasks().clone());
+        for text in ["Book h", "Book hotel"] {
+            app.tasks_mut()[0].subtasks[0].description = text.to_string();
+            app.tasks_mut()[0].touch();
+        }
+        app.finish_subtask_edit(0);
+        assert_eq!(app.undo_stack.len(), 1);
+        app.undo();
+        assert_eq!(app.tasks()[0].subtasks[0].description, "Book");
+
+        // Поле, которое просто потеряло фокус, не добавляет шаг отмены
+        app.subtask_snapshot = Some(app.tasks().clone());
+        app.finish_subtask_edit(0);
+        assert!(app.undo_stack.is_empty());
+    }
+
+    #[test]
+    fn edit_right_before_close_is_saved() {
+        let dir = std::env::temp_dir().join(format!("yuko-todo-exit-{}", std::process::id()));
+        let mut app = app_with(vec![Task::new("Pay rent".to_string())]);
+        app.save_path = dir.join("tasks.json");
+        app.flush_save();
+
+        // Правка сразу после записи ждет промежутка автосохранения
+        app.tasks_mut()[0].description = "Pay rent today".to_string();
+        app.tasks_mut()[0].touch();
+        app.autosave();
+        app.flush_if_due();
+        assert_eq!(read_task_file(&app.save_path).unwrap().tasks()[0].description, "Pay rent");
+
+        // Закрытие окна записывает ее, не дожидаясь промежутка
+        eframe::App::on_exit(&mut app, None);
+        assert_eq!(read_task_file(&app.save_path).unwrap().tasks()[0].description, "Pay rent today");
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -0,0 +1,260 @@
+// Экспорт и импорт задач в формате Markdown-чеклиста
+use crate::{effective_date, parse_timestamp, Task};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime};
+use std::collections::BTreeMap;
+
+// Список задач в виде `- [ ]` / `- [x]`; продолжения многострочных описаний идут с отступом
+pub fn to_checklist(tasks: &[Task]) -> String {
+    let mut markdown = String::new();
+    for task in tasks {
+        let mark = if task.completed { "x" } else { " " };
+        let mut lines = task.description.lines();
+        let first = lines.next().unwrap_or_default();
+        markdown.push_str(&format!("- [{}] {}", mark, escape(first)));
+        if task.completed {
+            if let Some(completed) = task.completed_label() {
+                markdown.push_str(&format!(" (completed {})", completed));
+            }
+        }
+        markdown.push('\n');
+        for line in lines {
+            markdown.push_str(&format!("  {}\n", escape(line)));
+        }
+    }
+    markdown
+}
+
+// Журнал выполненного: задачи сгруппированы по дню выполнения под заголовками `## ГГГГ-ММ-ДД`,
+// дни идут по порядку. День отсчитывается от часа смены дня `rollover_hour`.
+// Задачи без времени выполнения (из старых файлов) в журнал не попадают.
+pub fn to_journal(tasks: &[Task], rollover_hour: u8) -> String {
+    let mut days: BTreeMap<NaiveDate, Vec<(DateTime<Local>, &Task)>> = BTreeMap::new();
+    for task in tasks.iter().filter(|task| task.completed) {
+        if let Some(completed) = task.completed_at.as_deref().and_then(parse_timestamp) {
+            days.entry(effective_date(completed, rollover_hour)).or_default().push((completed, task));
+        }
+    }
+    let mut markdown = String::from("# Journal\n");
+    for (day, mut entries) in days {
+        entries.sort_by_key(|(time, _)| *time);
+        markdown.push_str(&format!("\n## {}\n\n", day.format("%Y-%m-%d")));
+        for (time, task) in entries {
+            let mut lines = task.description.lines();
+            markdown.push_str(&format!("- {} ({})\n", escape(lines.next().unwrap_or_default()), time.format("%H:%M")));
+            for line in lines {
+                markdown.push_str(&format!("  {}\n", escape(line)));
+            }
+            if let Some(note) = &task.completion_note {
+                markdown.push_str(&format!("  > {}\n", escape(&note.replace('\n', " "))));
+            }
+        }
+    }
+    markdown
+}
+
+// Сводка по списку: число задач, доля выполненных, средний возраст невыполненных задач
+// и самая старая из них. Возраст считается от `now`; задачи без времени создания не учитываются.
+pub fn to_stats(tasks: &[Task], now: DateTime<Local>) -> String {
+    let completed = tasks.iter().filter(|task| task.completed).count();
+    let rate = if tasks.is_empty() { 0.0 } else { completed as f64 * 100.0 / tasks.len() as f64 };
+    let open: Vec<(DateTime<Local>, &Task)> = tasks.iter()
+        .filter(|task| !task.completed)
+        .filter_map(|task| Some((parse_timestamp(&task.created_at)?, task)))
+        .collect();
+    let days = |created: DateTime<Local>| (now - created).num_seconds() as f64 / 86_400.0;
+
+    let mut markdown = String::from("# Statistics\n\n");
+    markdown.push_str(&format!("- Total tasks: {}\n", tasks.len()));
+    markdown.push_str(&format!("- Completed: {}\n", completed));
+    markdown.push_str(&format!("- Completion rate: {:.0}%\n", rate));
+    if open.is_empty() {
+        markdown.push_str("- Average age of open tasks: -\n- Oldest open task: -\n");
+    } else {
+        let average = open.iter().map(|(created, _)| days(*created)).sum::<f64>() / open.len() as f64;
+        markdown.push_str(&format!("- Average age of open tasks: {:.1} days\n", average));
+        if let Some((created, task)) = open.iter().min_by_key(|(created, _)| *created) {
+            let description = task.description.lines().next().unwrap_or_default();
+            markdown.push_str(&format!(
+                "- Oldest open task: {} (created {}, {} days ago)\n",
+                escape(description),
+                created.format("%Y-%m-%d"),
+                days(*created).floor()
+            ));
+        }
+    }
+    markdown
+}
+
+// Разбор текста: каждая непустая строка — задача, префиксы `- [ ]` / `- [x]` задают статус.
+// Строки с отступом после пункта чеклиста считаются продолжением его описания.
+pub fn parse_lines(text: &str) -> Vec<Task> {
+    let mut tasks: Vec<Task> = Vec::new();
+    let mut in_checklist_item = false;
+    for line in text.lines() {
+        if line.trim().is_empty() {
+            in_checklist_item = false;
+            continue;
+        }
+        let indented = line.starts_with(' ') || line.starts_with('\t');
+        if let Some((completed, rest)) = parse_checkbox(line.trim()) {
+            in_checklist_item = false;
+            let (description, completed_at) = split_completion(rest.trim(), completed);
+            if description.is_empty() {
+                continue; // Чекбокс без текста
+            }
+            let mut task = Task::new(unescape(description));
+            task.completed = completed;
+            task.completed_at = completed_at;
+            tasks.push(task);
+            in_checklist_item = true;
+        } else if indented && in_checklist_item {
+            if let Some(task) = tasks.last_mut() {
+                task.description.push('\n');
+                task.description.push_str(&unescape(line.trim()));
+            }
+        } else {
+            in_checklist_item = false;
+            tasks.push(Task::new(line.trim().to_string()));
+        }
+    }
+    tasks
+}
+
+// Префикс чеклиста: `- [ ]`, `- [x]`, `* [X]` и т.п.
+fn parse_checkbox(line: &str) -> Option<(bool, &str)> {
+    let rest = line.strip_prefix("- ").or_else(|| line.strip_prefix("* "))?;
+    let completed = match rest.get(..3)? {
+        "[ ]" => false,
+        "[x]" | "[X]" => true,
+        _ => return None,
+    };
+    Some((completed, &rest[3..]))
+}
+
+// Отделение суффикса " (completed ГГГГ-ММ-ДД ЧЧ:ММ)", который добавляет экспорт
+fn split_completion(text: &str, completed: bool) -> (&str, Option<String>) {
+    if completed {
+        if let Some(start) = text.rfind(" (completed ") {
+            let stamp = text[start + " (completed ".len()..].trim_end_matches(')');
+            if let Ok(time) = NaiveDateTime::parse_from_str(stamp, "%Y-%m-%d %H:%M") {
+                let time = time.and_local_timezone(Local).single().map(|time| time.to_rfc3339());
+                return (text[..start].trim_end(), time);
+            }
+        }
+    }
+    (text, None)
+}
+
+// Снятие экранирования, добавленного при экспорте
+fn unescape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\\' && matches!(chars.peek(), Some('\\' | '[' | ']' | '|')) {
+            result.extend(chars.next());
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+// Экранирование символов, которые Markdown воспринимает как разметку
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '[' | ']' | '|') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn completed(description: &str, day: u32, hour: u32) -> Task {
+        let mut task = Task::new(description.to_string());
+        task.completed = true;
+        task.completed_at = Some(Local.with_ymd_and_hms(2024, 3, day, hour, 30, 0).unwrap().to_rfc3339());
+        task
+    }
+
+    #[test]
+    fn journal_groups_completions_by_day() {
+        let mut with_note = completed("Call the bank", 11, 9);
+        with_note.completion_note = Some("Card reissued".to_string());
+        let tasks = vec![
+            completed("Ship release", 12, 15),
+            Task::new("Still open".to_string()),
+            with_note,
+            completed("Write report", 11, 17),
+        ];
+        assert_eq!(
+            to_journal(&tasks, 0),
+            "# Journal\n\
+             \n## 2024-03-11\n\n\
+             - Call the bank (09:30)\n  > Card reissued\n\
+             - Write report (17:30)\n\
+             \n## 2024-03-12\n\n\
+             - Ship release (15:30)\n"
+        );
+    }
+
+    #[test]
+    fn journal_skips_completions_without_time() {
+        let mut task = Task::new("Old task".to_string());
+        task.completed = true;
+        assert_eq!(to_journal(&[task], 0), "# Journal\n");
+    }
+
+    #[test]
+    fn journal_counts_night_completions_as_previous_day() {
+        let tasks = vec![completed("Late deploy", 12, 1), completed("Evening review", 11, 23)];
+        assert_eq!(
+            to_journal(&tasks, 3),
+            "# Journal\n\
+             \n## 2024-03-11\n\n\
+             - Evening review (23:30)\n\
+             - Late deploy (01:30)\n"
+        );
+    }
+
+    fn created(description: &str, day: u32) -> Task {
+        let mut task = Task::new(description.to_string());
+        task.created_at = Local.with_ymd_and_hms(2024, 3, day, 12, 0, 0).unwrap().to_rfc3339();
+        task
+    }
+
+    #[test]
+    fn stats_from_known_dates() {
+        let now = Local.with_ymd_and_hms(2024, 3, 21, 12, 0, 0).unwrap();
+        let mut done = created("Ship release", 1);
+        done.completed = true;
+        let mut unknown = created("Legacy task", 1);
+        unknown.created_at.clear();
+        let tasks = vec![created("Write report", 11), done, created("Pay rent", 5), unknown];
+        assert_eq!(
+            to_stats(&tasks, now),
+            "# Statistics\n\n\
+             - Total tasks: 4\n\
+             - Completed: 1\n\
+             - Completion rate: 25%\n\
+             - Average age of open tasks: 13.0 days\n\
+             - Oldest open task: Pay rent (created 2024-03-05, 16 days ago)\n"
+        );
+    }
+
+    #[test]
+    fn stats_without_open_tasks() {
+        let now = Local.with_ymd_and_hms(2024, 3, 21, 12, 0, 0).unwrap();
+        assert_eq!(
+            to_stats(&[], now),
+            "# Statistics\n\n- Total tasks: 0\n- Completed: 0\n- Completion rate: 0%\n\
+             - Average age of open tasks: -\n- Oldest open task: -\n"
+        );
+    }
+}
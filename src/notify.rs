@@ -0,0 +1,35 @@
+// Системные уведомления через утилиты ОС: notify-send в Linux и osascript в macOS.
+// Если утилиты нет или платформа не поддерживается, уведомление просто не показывается.
+use std::process::Command;
+
+// Показ уведомления в фоновом потоке, чтобы не задерживать перерисовку окна
+pub fn send(title: &str, body: &str) {
+    let Some(mut command) = command(title, body) else {
+        return;
+    };
+    std::thread::spawn(move || {
+        // Ошибки запуска игнорируются: уведомление необязательно
+        let _ = command.status();
+    });
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn command(title: &str, body: &str) -> Option<Command> {
+    let mut command = Command::new("notify-send");
+    command.args(["--app-name", "To-Do List", title, body]);
+    Some(command)
+}
+
+#[cfg(target_os = "macos")]
+fn command(title: &str, body: &str) -> Option<Command> {
+    let quote = |text: &str| format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""));
+    let script = format!("display notification {} with title {}", quote(body), quote(title));
+    let mut command = Command::new("osascript");
+    command.args(["-e", &script]);
+    Some(command)
+}
+
+#[cfg(not(unix))]
+fn command(_title: &str, _body: &str) -> Option<Command> {
+    None
+}
@@ -0,0 +1,350 @@
+// Минимальная генерация PDF с разбивкой на страницы. Текст набирается шрифтами egui
+// (Ubuntu, а для недостающих символов — шрифтами эмодзи), которые встраиваются в документ как
+// шрифты TrueType с кодировкой Identity-H. Поэтому кириллица и другие символы Unicode выводятся
+// как есть; символ, которого нет ни в одном шрифте, печатается пустым глифом.
+use eframe::egui::{FontDefinitions, FontFamily};
+use flate2::{write::ZlibEncoder, Compression};
+use std::collections::BTreeMap;
+use std::io::Write;
+
+const PAGE_WIDTH: f32 = 595.0; // A4 в пунктах
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 50.0;
+const TEXT_WIDTH: f32 = PAGE_WIDTH - 2.0 * MARGIN;
+const TITLE_SIZE: f32 = 18.0;
+const TEXT_SIZE: f32 = 11.0;
+const LINE_HEIGHT: f32 = 15.0;
+const INDENT: &str = "    "; // Отступ для продолжения перенесенной строки
+
+// Сборка PDF-документа из заголовка, подзаголовка и строк текста
+pub fn render(title: &str, subtitle: &str, lines: &[String]) -> Vec<u8> {
+    let definitions = FontDefinitions::default();
+    let mut fonts = Fonts::new(&definitions);
+    let wrapped: Vec<String> = lines.iter().flat_map(|line| fonts.wrap(line, TEXT_SIZE)).collect();
+
+    // Распределение строк по страницам; на первой место занимает заголовок
+    let first_page_lines = lines_per_page(MARGIN + TITLE_SIZE + 2.0 * LINE_HEIGHT);
+    let other_page_lines = lines_per_page(MARGIN);
+    let mut pages: Vec<&[String]> = Vec::new();
+    let (first, mut rest) = wrapped.split_at(first_page_lines.min(wrapped.len()));
+    pages.push(first);
+    while !rest.is_empty() {
+        let (page, tail) = rest.split_at(other_page_lines.min(rest.len()));
+        pages.push(page);
+        rest = tail;
+    }
+
+    let mut streams = Vec::new();
+    for (n, page) in pages.iter().enumerate() {
+        let mut content = String::new();
+        let mut y = PAGE_HEIGHT - MARGIN;
+        if n == 0 {
+            y -= TITLE_SIZE;
+            content.push_str(&fonts.text_op(TITLE_SIZE, MARGIN, y, title));
+            y -= LINE_HEIGHT;
+            content.push_str(&fonts.text_op(TEXT_SIZE, MARGIN, y, subtitle));
+            y -= LINE_HEIGHT;
+        }
+        for line in page.iter() {
+            y -= LINE_HEIGHT;
+            content.push_str(&fonts.text_op(TEXT_SIZE, MARGIN, y, line));
+        }
+        streams.push(content.into_bytes());
+    }
+
+    // Объекты: 1 — каталог, 2 — дерево страниц, далее пары страница/содержимое,
+    // затем по пять объектов на каждый использованный шрифт
+    let first_font = 3 + 2 * streams.len();
+    let used: Vec<&Font> = fonts.fonts.iter().filter(|font| !font.used.is_empty()).collect();
+    let resources: Vec<String> = used.iter()
+        .enumerate()
+        .map(|(k, font)| format!("/F{} {} 0 R", font.id, first_font + 5 * k))
+        .collect();
+
+    let mut objects: Vec<Vec<u8>> = Vec::new();
+    objects.push(b"<< /Type /Catalog /Pages 2 0 R >>".to_vec());
+    let kids: Vec<String> = (0..streams.len()).map(|n| format!("{} 0 R", 3 + 2 * n)).collect();
+    objects.push(format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), streams.len()).into_bytes());
+    for (n, stream) in streams.iter().enumerate() {
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << {} >> >> /Contents {} 0 R >>",
+            PAGE_WIDTH, PAGE_HEIGHT, resources.join(" "), 4 + 2 * n
+        ).into_bytes());
+        objects.push(stream_object("", stream));
+    }
+    for (k, font) in used.iter().enumerate() {
+        let id = first_font + 5 * k;
+        objects.extend(font.objects(id));
+    }
+
+    let mut pdf = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec(); // Вторая строка помечает файл как двоичный
+    let mut offsets = Vec::new();
+    for (n, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend_from_slice(format!("{} 0 obj\n", n + 1).as_bytes());
+        pdf.extend_from_slice(object);
+        pdf.extend_from_slice(b"\nendobj\n");
+    }
+    let xref = pdf.len();
+    pdf.extend_from_slice(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes());
+    for offset in offsets {
+        pdf.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+    }
+    pdf.extend_from_slice(format!(
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref
+    ).as_bytes());
+    pdf
+}
+
+// Шрифт TrueType и глифы, которые в нем использованы
+struct Font<'a> {
+    id: usize, // Номер в имени ресурса (/F1, /F2, ...)
+    name: String,
+    data: &'a [u8],
+    face: ttf_parser::Face<'a>,
+    used: BTreeMap<u16, char>, // Глиф и символ, которому он соответствует (для копирования текста)
+}
+
+impl Font<'_> {
+    // Ширина глифа в тысячных долях кегля, как ее ждет PDF
+    fn advance(&self, glyph: u16) -> f32 {
+        let advance = self.face.glyph_hor_advance(ttf_parser::GlyphId(glyph)).unwrap_or(0);
+        self.to_pdf_units(advance as f32)
+    }
+
+    fn to_pdf_units(&self, value: f32) -> f32 {
+        (value * 1000.0 / self.face.units_per_em() as f32).round()
+    }
+
+    // Объекты шрифта начиная с номера `id`: составной шрифт, шрифт CID, описание шрифта,
+    // файл шрифта и таблица соответствия глифов символам
+    fn objects(&self, id: usize) -> Vec<Vec<u8>> {
+        let widths: Vec<String> = self.used.keys().map(|&glyph| format!("{} [{}]", glyph, self.advance(glyph))).collect();
+        let bbox = self.face.global_bounding_box();
+        let bbox = [bbox.x_min, bbox.y_min, bbox.x_max, bbox.y_max].map(|value| self.to_pdf_units(value as f32));
+        let ascent = self.to_pdf_units(self.face.ascender() as f32);
+        let descent = self.to_pdf_units(self.face.descender() as f32);
+        let cap_height = self.to_pdf_units(self.face.capital_height().unwrap_or(self.face.ascender()) as f32);
+        vec![
+            format!(
+                "<< /Type /Font /Subtype /Type0 /BaseFont /{} /Encoding /Identity-H /DescendantFonts [{} 0 R] /ToUnicode {} 0 R >>",
+                self.name, id + 1, id + 4
+            ).into_bytes(),
+            format!(
+                "<< /Type /Font /Subtype /CIDFontType2 /BaseFont /{} /CIDSystemInfo << /Registry (Adobe) /Ordering (Identity) /Supplement 0 >> \
+                 /FontDescriptor {} 0 R /CIDToGIDMap /Identity /W [{}] >>",
+                self.name, id + 2, widths.join(" ")
+            ).into_bytes(),
+            format!(
+                "<< /Type /FontDescriptor /FontName /{} /Flags 32 /FontBBox [{} {} {} {}] /ItalicAngle 0 \
+                 /Ascent {} /Descent {} /CapHeight {} /StemV 80 /FontFile2 {} 0 R >>",
+                self.name, bbox[0], bbox[1], bbox[2], bbox[3], ascent, descent, cap_height, id + 3
+            ).into_bytes(),
+            stream_object(&format!("/Length1 {}", self.data.len()), self.data),
+            stream_object("", self.to_unicode().as_bytes()),
+        ]
+    }
+
+    // CMap для копирования и поиска текста в просмотрщике: глиф -> символ (UTF-16BE)
+    fn to_unicode(&self) -> String {
+        let mut cmap = String::from(
+            "/CIDInit /ProcSet findresource begin\n12 dict begin\nbegincmap\n\
+             /CIDSystemInfo << /Registry (Adobe) /Ordering (UCS) /Supplement 0 >> def\n\
+             /CMapName /Adobe-Identity-UCS def\n/CMapType 2 def\n\
+             1 begincodespacerange\n<0000> <FFFF>\nendcodespacerange\n",
+        );
+        let entries: Vec<(&u16, &char)> = self.used.iter().collect();
+        // В одном блоке bfchar допускается не больше 100 записей
+        for block in entries.chunks(100) {
+            cmap.push_str(&format!("{} beginbfchar\n", block.len()));
+            for (glyph, c) in block {
+                let utf16: String = c.encode_utf16(&mut [0; 2]).iter().map(|unit| format!("{:04X}", unit)).collect();
+                cmap.push_str(&format!("<{:04X}> <{}>\n", glyph, utf16));
+            }
+            cmap.push_str("endbfchar\n");
+        }
+        cmap.push_str("endcmap\nCMapName currentdict /CMap defineresource pop\nend\nend\n");
+        cmap
+    }
+}
+
+// Шрифты в порядке, в котором egui подбирает их для обычного текста
+struct Fonts<'a> {
+    fonts: Vec<Font<'a>>,
+}
+
+impl<'a> Fonts<'a> {
+    fn new(definitions: &'a FontDefinitions) -> Self {
+        let names = definitions.families.get(&FontFamily::Proportional).into_iter().flatten();
+        let fonts = names
+            .filter_map(|name| {
+                let font_data = definitions.font_data.get(name)?;
+                let face = ttf_parser::Face::parse(&font_data.font, font_data.index).ok()?;
+                Some((name, &font_data.font[..], face))
+            })
+            .enumerate()
+            .map(|(n, (name, data, face))| Font {
+                id: n + 1,
+                // Имя PDF не может содержать пробелов и скобок
+                name: name.chars().filter(|c| c.is_ascii_alphanumeric() || *c == '-').collect(),
+                data,
+                face,
+                used: BTreeMap::new(),
+            })
+            .collect();
+        Fonts { fonts }
+    }
+
+    // Шрифт и глиф для символа: первый шрифт, в котором символ есть, иначе пустой глиф основного
+    fn glyph(&self, c: char) -> (usize, u16) {
+        self.fonts.iter()
+            .enumerate()
+            .find_map(|(n, font)| font.face.glyph_index(c).map(|glyph| (n, glyph.0)))
+            .unwrap_or((0, 0))
+    }
+
+    // Ширина текста в пунктах при заданном кегле
+    fn width(&self, text: &str, size: f32) -> f32 {
+        if self.fonts.is_empty() {
+            return 0.0;
+        }
+        let units: f32 = text.chars()
+            .map(|c| {
+                let (font, glyph) = self.glyph(c);
+                self.fonts[font].advance(glyph)
+            })
+            .sum();
+        units * size / 1000.0
+    }
+
+    // Перенос строки по словам в пределах ширины страницы; слово длиннее строки
+    // разбивается по символам. Продолжения строки идут с отступом.
+    fn wrap(&self, line: &str, size: f32) -> Vec<String> {
+        let fits = |text: &str| self.width(text, size) <= TEXT_WIDTH;
+        let mut result = Vec::new();
+        let mut current = String::new();
+        let mut at_start = true; // В строке пока только отступ
+        for word in line.split(' ') {
+            let joined = if at_start { format!("{}{}", current, word) } else { format!("{} {}", current, word) };
+            if fits(&joined) {
+                current = joined;
+                at_start = false;
+                continue;
+            }
+            if !at_start {
+                result.push(std::mem::replace(&mut current, INDENT.to_string()));
+                let joined = format!("{}{}", current, word);
+                if fits(&joined) {
+                    current = joined;
+                    at_start = false;
+                    continue;
+                }
+            }
+            for c in word.chars() {
+                current.push(c);
+                if !fits(&current) {
+                    current.pop();
+                    result.push(std::mem::replace(&mut current, format!("{}{}", INDENT, c)));
+                }
+            }
+            at_start = false;
+        }
+        result.push(current);
+        result
+    }
+
+    // Оператор вывода одной строки текста. Отрезки, набранные разными шрифтами, идут подряд:
+    // после каждого глифа позиция сдвигается на его ширину из таблицы /W.
+    fn text_op(&mut self, size: f32, x: f32, y: f32, text: &str) -> String {
+        if self.fonts.is_empty() {
+            return String::new();
+        }
+        let mut op = format!("BT {} {} Td", x, y);
+        let mut current_font = None;
+        for c in text.chars().map(|c| if c.is_control() { ' ' } else { c }) {
+            let (font, glyph) = self.glyph(c);
+            if current_font != Some(font) {
+                if current_font.is_some() {
+                    op.push_str("> Tj");
+                }
+                op.push_str(&format!(" /F{} {} Tf <", self.fonts[font].id, size));
+                current_font = Some(font);
+            }
+            self.fonts[font].used.entry(glyph).or_insert(c);
+            op.push_str(&format!("{:04X}", glyph));
+        }
+        if current_font.is_some() {
+            op.push_str("> Tj");
+        }
+        op.push_str(" ET\n");
+        op
+    }
+}
+
+// Количество строк текста, помещающихся на странице ниже отступа `top`
+fn lines_per_page(top: f32) -> usize {
+    ((PAGE_HEIGHT - top - MARGIN) / LINE_HEIGHT) as usize
+}
+
+// Поток, сжатый Deflate; `extra` добавляется в словарь потока
+fn stream_object(extra: &str, data: &[u8]) -> Vec<u8> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    // Запись в Vec не может завершиться ошибкой
+    let compressed = encoder.write_all(data).and_then(|_| encoder.finish()).unwrap_or_default();
+    let mut object = format!("<< /Length {} /Filter /FlateDecode {} >>\nstream\n", compressed.len(), extra).into_bytes();
+    object.extend_from_slice(&compressed);
+    object.extend_from_slice(b"\nendstream");
+    object
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contains(haystack: &[u8], needle: &str) -> bool {
+        haystack.windows(needle.len()).any(|window| window == needle.as_bytes())
+    }
+
+    #[test]
+    fn renders_small_list() {
+        let lines = vec!["[ ] Купить молоко".to_string(), "[x] Ship the release".to_string()];
+        let pdf = render("To-Do List", "Exported 2024-03-12 10:00", &lines);
+        assert!(pdf.starts_with(b"%PDF-1.4\n"));
+        assert!(pdf.ends_with(b"%%EOF\n"));
+        assert!(contains(&pdf, "/Count 1 "));
+        assert!(contains(&pdf, "/Encoding /Identity-H"));
+        assert!(contains(&pdf, "/FontFile2"));
+    }
+
+    #[test]
+    fn paginates_long_lists() {
+        let lines: Vec<String> = (0..200).map(|n| format!("[ ] Task {}", n)).collect();
+        let pdf = render("To-Do List", "", &lines);
+        assert!(contains(&pdf, "/Count 5 "));
+    }
+
+    #[test]
+    fn cyrillic_has_glyphs() {
+        let definitions = FontDefinitions::default();
+        let fonts = Fonts::new(&definitions);
+        for c in "Список задач".chars().filter(|c| !c.is_whitespace()) {
+            assert_ne!(fonts.glyph(c).1, 0, "no glyph for {}", c);
+        }
+    }
+
+    #[test]
+    fn wrap_breaks_long_words() {
+        let definitions = FontDefinitions::default();
+        let fonts = Fonts::new(&definitions);
+        let word = "ж".repeat(300);
+        let wrapped = fonts.wrap(&format!("[ ] {} end", word), TEXT_SIZE);
+        assert!(wrapped.len() > 2);
+        for line in &wrapped {
+            assert!(fonts.width(line, TEXT_SIZE) <= TEXT_WIDTH, "line too wide: {}", line);
+        }
+        let text: String = wrapped.iter().map(|line| line.trim_start()).collect::<Vec<_>>().join("");
+        assert_eq!(text, format!("[ ]{}end", word));
+    }
+}